@@ -18,7 +18,7 @@ fn main() -> Result<()> {
     let asphyxia_config = cfg.asyphyxia;
     let refid = asphyxia_config.refid;
 
-    let ds = BemaniutilsDataSource::open(bemanitutils_config)?;
+    let ds = BemaniutilsDataSource::open(bemanitutils_config, &cfg.search)?;
     let records = ds.get_records();
     let save_data_file = fs::OpenOptions::new()
         .create(true)
@@ -27,18 +27,22 @@ fn main() -> Result<()> {
         .open(asphyxia_config.record_path)?;
 
     let mut writer = BufWriter::new(save_data_file);
+    let mut skipped = 0;
     for (idx, r) in records.iter().enumerate() {
-        let asphyxia_record = storage::AsphyxiaRecord::new_sdvx_record(
-            refid.clone(),
-            r.get_music_id(),
-            r.get_difficulty().into(),
-            r.get_score(),
-            r.get_clear_type().into(),
-            r.get_grade().into(),
-            format!("{}", idx),
-        );
+        // the records of unknown difficulty can not be saved as a music type
+        let asphyxia_record =
+            match storage::AsphyxiaRecord::from_full_record(refid.clone(), r, format!("{}", idx)) {
+                Some(rec) => rec,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
         serde_json::to_writer(&mut writer, &asphyxia_record)?;
-        writer.write(b"\n")?;
+        writer.write_all(b"\n")?;
+    }
+    if skipped > 0 {
+        eprintln!("{} record(s) of unknown difficulty skipped.", skipped);
     }
     writer.flush()?;
     Ok(())
@@ -4,15 +4,31 @@ use ruborute::{Cmdline, Config};
 
 fn main() {
     let mut cfg = Config::load_from_args();
+    let execute = cfg.execute.take();
+    let script = cfg.script.take();
     if !cfg.config_file.is_empty() {
         let config_file = cfg.config_file;
+        let output = cfg.output;
+        let source = cfg.source;
+        let strict = cfg.asyphyxia.strict;
         cfg = Config::load_from_file(config_file.as_str()).unwrap();
         cfg.config_file = config_file;
+        // the flags from args override the ones from config file
+        cfg.output = output.or(cfg.output);
+        cfg.source = source.or(cfg.source);
+        cfg.asyphyxia.strict |= strict;
     }
     let cmdline = Cmdline::new(cfg);
     match cmdline {
         Ok(mut cl) => {
-            if let Err(e) = cl.run() {
+            let res = if let Some(line) = execute {
+                cl.execute(line.as_str())
+            } else if let Some(path) = script {
+                cl.run_script(path.as_str())
+            } else {
+                cl.run()
+            };
+            if let Err(e) = res {
                 eprintln!("{}", e);
                 exit(1)
             }
@@ -1,7 +1,7 @@
-use crate::config::Config;
+use crate::config::{Config, SourceType};
 use crate::data_source::DataSource;
-use crate::{command::*, data_source, Result};
-use prettytable::{cell, row, Cell, Row, Table};
+use crate::{command::*, data_source, Error, Result};
+use prettytable::{row, Cell, Row, Table};
 use rustyline::{
     completion::Completer,
     error::ReadlineError,
@@ -9,21 +9,62 @@ use rustyline::{
     Editor,
 };
 use rustyline_derive::{Helper, Highlighter, Validator};
-use std::{collections::HashMap, rc::Rc, vec};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    rc::Rc,
+    vec,
+};
 
 struct CmdCompleter {
     commands: Vec<String>,
+    cmds: HashMap<String, Rc<dyn Cmd>>,
 }
 
 impl CmdCompleter {
     fn new() -> Self {
         CmdCompleter {
             commands: Vec::new(),
+            cmds: HashMap::new(),
         }
     }
     fn add_command(&mut self, cmd: String) {
         self.commands.push(cmd)
     }
+    /// add a command whose args can be completed.
+    fn add_cmd(&mut self, cmd: Rc<dyn Cmd>) {
+        self.add_command(cmd.name().to_string());
+        self.cmds.insert(cmd.name().to_string(), cmd);
+    }
+}
+
+impl CmdCompleter {
+    /// complete the command name, or the args by the command if the name is finished.
+    fn complete_line(&self, line: &str) -> (usize, Vec<String>) {
+        let start = line.len() - line.trim_start().len();
+        let line = line.trim_start();
+        // the separator may be a full-width space, which is more than one byte.
+        if let Some((i, sep)) = line.char_indices().find(|(_, c)| c.is_whitespace()) {
+            let name = &line[..i];
+            return match self.cmds.get(name) {
+                Some(cmd) => {
+                    let args_start = i + sep.len_utf8();
+                    let (pos, candidates) = cmd.complete(&line[args_start..]);
+                    (start + args_start + pos, candidates)
+                }
+                None => (start + line.len(), Vec::new()),
+            };
+        }
+        let res = self
+            .commands
+            .iter()
+            .filter(|s| s.starts_with(line))
+            .map(|s| s.clone() + " ")
+            .collect::<Vec<String>>();
+        (start, res)
+    }
 }
 
 impl Completer for CmdCompleter {
@@ -32,16 +73,10 @@ impl Completer for CmdCompleter {
     fn complete(
         &self,
         line: &str,
-        _pos: usize,
+        pos: usize,
         _ctx: &rustyline::Context<'_>,
     ) -> rustyline::Result<(usize, Vec<Self::Candidate>)> {
-        let res = self
-            .commands
-            .iter()
-            .filter(|s| s.starts_with(line))
-            .map(|s| s.clone() + " ")
-            .collect::<Vec<String>>();
-        Ok((0, res))
+        Ok(self.complete_line(&line[..pos]))
     }
 }
 
@@ -55,6 +90,9 @@ impl CmdlineHelper {
     fn add_command(&mut self, cmd: String) {
         self.cmd_completer.add_command(cmd);
     }
+    fn add_cmd(&mut self, cmd: Rc<dyn Cmd>) {
+        self.cmd_completer.add_cmd(cmd);
+    }
 }
 
 impl Completer for CmdlineHelper {
@@ -78,9 +116,19 @@ impl Hinter for CmdlineHelper {
     }
 }
 
+/// the help information of one command.
+#[derive(Serialize)]
+struct CmdHelp {
+    name: String,
+    usage: String,
+    description: String,
+}
+
 pub struct Cmdline {
     help_table: Table,
-    cmds: HashMap<String, Box<dyn Cmd>>,
+    helps: Vec<CmdHelp>,
+    printer: Printer,
+    cmds: HashMap<String, Rc<dyn Cmd>>,
     rl: Editor<CmdlineHelper>,
 }
 
@@ -97,26 +145,54 @@ impl Cmdline {
             cmd_completer: CmdCompleter::new(),
             cmd_hinter: HistoryHinter {},
         };
-        let cmds: HashMap<String, Box<dyn Cmd>> = HashMap::new();
+        let cmds: HashMap<String, Rc<dyn Cmd>> = HashMap::new();
         let mut help_table = Table::new();
         help_table.add_row(row!["name", "usage", "description"]);
         help_table.add_row(row!["help", "help", "show the help information."]);
+        let helps = vec![CmdHelp {
+            name: String::from("help"),
+            usage: String::from("help"),
+            description: String::from("show the help information."),
+        }];
         helper.add_command(String::from("help"));
         let mut rl = Editor::with_config(rustyline_config);
         rl.set_helper(Some(helper));
         let mut cmdline = Cmdline {
             cmds,
             help_table,
+            helps,
+            printer: Printer::new(cfg.output.unwrap_or_default()),
             rl,
         };
 
-        match data_source::AsphyxiaDataSource::open(cfg.asyphyxia) {
-            // load from asyphyxia first
-            Ok(s) => cmdline.add_commands(Rc::new(s)),
-            // if load asyphyxia, load from bemaniutils server
-            _ => cmdline.add_commands(Rc::new(data_source::BemaniutilsDataSource::open(
-                cfg.bemaniutils,
-            )?)),
+        match cfg.source.unwrap_or_default() {
+            SourceType::Asphyxia => cmdline.add_commands(Rc::new(
+                data_source::AsphyxiaDataSource::open(cfg.asyphyxia, &cfg.search)?,
+            )),
+            SourceType::Bemaniutils => cmdline.add_commands(Rc::new(
+                data_source::BemaniutilsDataSource::open(cfg.bemaniutils, &cfg.search)?,
+            )),
+            // load from asyphyxia first, and then bemaniutils server
+            SourceType::Auto => {
+                match data_source::AsphyxiaDataSource::open(cfg.asyphyxia, &cfg.search) {
+                    Ok(s) => cmdline.add_commands(Rc::new(s)),
+                    Err(asphyxia_err) => {
+                        eprintln!("load data from Asphyxia failed: {}", asphyxia_err);
+                        match data_source::BemaniutilsDataSource::open(cfg.bemaniutils, &cfg.search)
+                        {
+                            Ok(s) => cmdline.add_commands(Rc::new(s)),
+                            Err(bemaniutils_err) => {
+                                return Err(Error::OtherError(format!(
+                                    "no data source available.\n\
+                                 Asphyxia: {}\n\
+                                 Bemaniutils: {}",
+                                    asphyxia_err, bemaniutils_err
+                                )))
+                            }
+                        }
+                    }
+                }
+            }
         };
 
         Ok(cmdline)
@@ -124,26 +200,50 @@ impl Cmdline {
 
     /// add all supported commands
     fn add_commands<D: 'static + DataSource>(&mut self, ds: Rc<D>) {
-        self.add_command(Box::new(CmdRecord::new(Rc::clone(&ds))));
-        self.add_command(Box::new(CmdBest50::new(Rc::clone(&ds))));
-        self.add_command(Box::new(CmdVolforce::new(Rc::clone(&ds))));
-        self.add_command(Box::new(CmdCount::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdRecord::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdBest50::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdBest::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdVolforce::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdCount::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdMusic::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdTarget::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdSuggest::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdFolder::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdSnapshot::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdDiff::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdRecent::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdVolforceHistory::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdRival::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdUsers::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdUser::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdProfile::new(Rc::clone(&ds))));
+        self.add_command(Rc::new(CmdSkill::new(Rc::clone(&ds))));
+        if let Ok(profile) = ds.get_profile() {
+            if !profile.name.is_empty() {
+                eprintln!("Welcome, {}!", profile.name);
+            }
+        }
     }
 
-    fn add_command(&mut self, cmd: Box<dyn Cmd>) {
+    fn add_command(&mut self, cmd: Rc<dyn Cmd>) {
         self.help_table.add_row(Row::new(vec![
             Cell::new(cmd.name()),
             Cell::new(cmd.usage()),
             Cell::new(cmd.description()),
         ]));
+        self.helps.push(CmdHelp {
+            name: cmd.name().to_string(),
+            usage: cmd.usage().to_string(),
+            description: cmd.description().to_string(),
+        });
         if let Some(helper) = self.rl.helper_mut() {
-            helper.add_command(cmd.name().to_string());
+            helper.add_cmd(Rc::clone(&cmd));
         }
         self.cmds.insert(cmd.name().to_string(), cmd);
     }
 
-    fn help(&self) {
-        self.help_table.printstd();
+    fn help(&self) -> Result<()> {
+        self.printer.print(&self.help_table, &self.helps)
     }
 
     pub fn run(&mut self) -> Result<()> {
@@ -153,13 +253,9 @@ impl Cmdline {
             match readline {
                 Ok(line) => {
                     self.rl.add_history_entry(line.as_str());
-                    let cmds: Vec<String> = line
-                        .trim()
-                        .split(" ")
-                        .filter(|&s| s.ne(""))
-                        .map(|s| String::from(s))
-                        .collect();
-                    self.interact(cmds);
+                    if let Err(e) = self.execute(line.as_str()) {
+                        println!("{}", e);
+                    }
                 }
                 Err(ReadlineError::Interrupted) => {
                     println!("<Keyboard Interrupted>");
@@ -179,20 +275,121 @@ impl Cmdline {
         Ok(())
     }
 
-    fn interact(&self, cmds: Vec<String>) {
-        if cmds.len() == 0 {
-            return;
+    /// execute the commands in the script file at `path` line by line,
+    /// `-` means reading the script from stdin.
+    ///
+    /// Empty lines and lines starting with `#` are skipped.
+    /// All the lines are executed even if some of them failed,
+    /// and an error is returned at the end if any of them failed.
+    pub fn run_script(&self, path: &str) -> Result<()> {
+        if path == "-" {
+            self.run_lines(io::stdin().lock())
+        } else {
+            self.run_lines(BufReader::new(File::open(path)?))
+        }
+    }
+
+    fn run_lines(&self, reader: impl BufRead) -> Result<()> {
+        let mut failed = 0;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().starts_with('#') {
+                continue;
+            }
+            if let Err(e) = self.execute(line.as_str()) {
+                eprintln!("line {}: {}", i + 1, e);
+                failed += 1;
+            }
+        }
+        if failed > 0 {
+            return Err(Error::OtherError(format!("{} command(s) failed.", failed)));
+        }
+        Ok(())
+    }
+
+    /// execute one line of command.
+    pub fn execute(&self, line: &str) -> Result<()> {
+        let cmds: Vec<String> = line.split_whitespace().map(String::from).collect();
+        self.interact(cmds)
+    }
+
+    fn interact(&self, cmds: Vec<String>) -> Result<()> {
+        if cmds.is_empty() {
+            return Ok(());
         }
         if cmds[0].as_str() == "help" {
-            self.help();
-            return;
+            return self.help();
         }
         if let Some(cmd) = self.cmds.get(&cmds[0]) {
-            if let Err(e) = cmd.do_cmd(&cmds[1..]) {
-                println!("{}", e);
-            }
+            cmd.do_cmd(&cmds[1..], &self.printer)
         } else {
-            println!("no such command");
+            Err(Error::OtherError(String::from("no such command")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// completes the args as themselves.
+    struct CmdEcho;
+
+    impl Cmd for CmdEcho {
+        fn name(&self) -> &str {
+            "echo"
+        }
+        fn usage(&self) -> &str {
+            "echo <args>"
         }
+        fn description(&self) -> &str {
+            "echo the args."
+        }
+        fn do_cmd(&self, _: &[String], _: &Printer) -> Result<()> {
+            Ok(())
+        }
+        fn complete(&self, args: &str) -> (usize, Vec<String>) {
+            (0, vec![args.to_string()])
+        }
+    }
+
+    fn completer() -> CmdCompleter {
+        let mut completer = CmdCompleter::new();
+        completer.add_command(String::from("help"));
+        completer.add_cmd(Rc::new(CmdEcho));
+        completer
+    }
+
+    #[test]
+    fn test_complete_command_name() {
+        let completer = completer();
+        assert_eq!(
+            completer.complete_line("ec"),
+            (0, vec![String::from("echo ")])
+        );
+        assert_eq!(
+            completer.complete_line("  he"),
+            (2, vec![String::from("help ")])
+        );
+    }
+
+    #[test]
+    fn test_complete_args_after_space() {
+        let completer = completer();
+        let line = "echo Black";
+        let (pos, candidates) = completer.complete_line(line);
+        assert_eq!(pos, 5);
+        assert_eq!(candidates, vec![String::from("Black")]);
+        assert_eq!(&line[pos..], "Black");
+    }
+
+    #[test]
+    fn test_complete_args_after_full_width_space() {
+        let completer = completer();
+        let line = "echo\u{3000}ブラック";
+        let (pos, candidates) = completer.complete_line(line);
+        assert_eq!(pos, "echo\u{3000}".len());
+        assert_eq!(candidates, vec![String::from("ブラック")]);
+        assert_eq!(&line[pos..], "ブラック");
     }
 }
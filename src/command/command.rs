@@ -1,10 +1,26 @@
-use std::rc::Rc;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
-use prettytable::{cell, row, table};
+use chrono::{
+    DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+};
+use prettytable::{row, table, Cell, Table};
 
-use crate::{data_source::DataSource, Error, Result};
+use crate::{
+    data_source::DataSource,
+    model::{
+        music::{Difficulty, Music},
+        record::{ClearType, FullRecord, Grade, LevelStat, Volfoce},
+        snapshot::Snapshot,
+    },
+    Error, Result,
+};
+use serde::Serialize;
 
-use super::Cmd;
+use super::{completion, filter::ChartFilter, Cmd, OutputFormat, Printer};
 
 /// `CmdRecord` is used to get gaming data from storage.
 pub struct CmdRecord<T: DataSource> {
@@ -28,8 +44,18 @@ impl<T: DataSource> Cmd for CmdRecord<T> {
         "get music record by the music id or name."
     }
 
-    fn do_cmd(&self, args: &[String]) -> Result<()> {
-        if args.len() < 1 {
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct RecordRow<'a> {
+            #[serde(flatten)]
+            record: &'a FullRecord,
+            artist: String,
+            bpm: String,
+            version: u8,
+            released: Option<NaiveDate>,
+        }
+
+        if args.is_empty() {
             return Err(Error::DoCmdError(String::from("args unmatched.")));
         }
         let records = if let Ok(music_id) = args[0].as_str().parse::<u16>() {
@@ -41,31 +67,83 @@ impl<T: DataSource> Cmd for CmdRecord<T> {
             let name = args.join(" ");
             self.store.get_record_by_name(name)
         };
-        if records.len() > 0 {
-            let mut tab = table!([
+        // the records of a music may not be adjacent when they are searched by name
+        let mut seen = HashSet::new();
+        let mut music_id = records
+            .iter()
+            .map(|r| r.get_music_id())
+            .collect::<Vec<u16>>();
+        music_id.retain(|id| seen.insert(*id));
+        let music = self.store.get_music_by_id(music_id);
+        for m in music.iter() {
+            printer.info(music_summary(m));
+        }
+        if !records.is_empty() {
+            // the music metadata is in the summary for humans, but in every row of the exports
+            let with_metadata = printer.format() != OutputFormat::Table;
+            let mut header = row![
                 "music id",
                 "music name",
                 "difficulty",
                 "level",
                 "score",
+                "ex score",
                 "grade",
                 "clear type",
-                "volforce"
-            ]);
+                "volforce",
+                "btn/long/vol rate",
+                "first played",
+                "last updated"
+            ];
+            if with_metadata {
+                for title in ["artist", "bpm", "version", "released"] {
+                    header.add_cell(Cell::new(title));
+                }
+            }
+            let mut tab = Table::new();
+            tab.add_row(header);
+            let mut rows = Vec::new();
             for rec in &records {
-                tab.add_row(row![
+                let m = music.iter().find(|m| m.get_id() == rec.get_music_id());
+                let row = RecordRow {
+                    record: rec,
+                    artist: m.map(|m| m.get_artist()).unwrap_or_default(),
+                    bpm: m.map(|m| m.get_bpm()).unwrap_or_default(),
+                    version: m.map_or(0, |m| m.get_version()),
+                    released: m.and_then(|m| m.get_distribution_date()),
+                };
+                let mut cells = row![
                     rec.get_music_id(),
                     rec.get_music_name_str(),
                     rec.get_difficulty(),
                     rec.get_level(),
                     rec.get_score(),
+                    rec.get_exscore(),
                     rec.get_grade(),
                     rec.get_clear_type(),
                     rec.get_volforce(),
-                ]);
+                    format!(
+                        "{}/{}/{}",
+                        rec.get_button_rate(),
+                        rec.get_long_rate(),
+                        rec.get_vol_rate()
+                    ),
+                    format_date(rec.get_created_at()),
+                    format_date(rec.get_updated_at()),
+                ];
+                if with_metadata {
+                    cells.add_cell(Cell::new(&row.artist));
+                    cells.add_cell(Cell::new(&row.bpm));
+                    cells.add_cell(Cell::new(&row.version.to_string()));
+                    cells.add_cell(Cell::new(
+                        &row.released.map_or(String::from("-"), |d| d.to_string()),
+                    ));
+                }
+                tab.add_row(cells);
+                rows.push(row);
             }
-            tab.printstd();
-            println!("{} record(s) founded.", records.len());
+            printer.print(&tab, &rows)?;
+            printer.info(format!("{} record(s) founded.", records.len()));
         } else {
             return Err(Error::DoCmdError(String::from(
                 "The music record not found.",
@@ -73,6 +151,10 @@ impl<T: DataSource> Cmd for CmdRecord<T> {
         }
         Ok(())
     }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        completion::complete_music(self.store.as_ref(), args)
+    }
 }
 
 pub struct CmdBest50<T: DataSource> {
@@ -96,42 +178,156 @@ impl<T: DataSource> Cmd for CmdBest50<T> {
         "get the best 50 records in volforce order."
     }
 
-    fn do_cmd(&self, _: &[String]) -> Result<()> {
-        let records = self.store.get_best50_records();
-        if records.len() > 0 {
-            let mut tab = table!([
-                "rank",
-                "music id",
-                "music name",
-                "difficulty",
-                "level",
-                "score",
-                "grade",
-                "clear type",
-                "volforce"
-            ]);
-            for (i, rec) in records.iter().enumerate() {
-                tab.add_row(row![
-                    format!("#{}", i + 1),
-                    rec.get_music_id(),
-                    rec.get_music_name_str(),
-                    rec.get_difficulty(),
-                    rec.get_level(),
-                    rec.get_score(),
-                    rec.get_grade(),
-                    rec.get_clear_type(),
-                    rec.get_volforce(),
-                ]);
-            }
-            tab.printstd();
-            println!("{} record(s) founded.", records.len());
-        } else {
-            return Err(Error::DoCmdError(String::from(
-                "The music record not found.",
-            )));
+    fn do_cmd(&self, _: &[String], printer: &Printer) -> Result<()> {
+        print_ranked_records(
+            printer,
+            (1..).zip(self.store.get_best50_records()).collect(),
+        )
+    }
+}
+
+/// `CmdBest` is used to get records in a range of the volforce order.
+pub struct CmdBest<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdBest<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdBest { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdBest<T> {
+    fn name(&self) -> &str {
+        "best"
+    }
+    fn usage(&self) -> &str {
+        "best <from> <to> [key=value ...]"
+    }
+    fn description(&self) -> &str {
+        "range get the records in volforce order."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        let (filter, args) = ChartFilter::parse(args)?;
+        if args.len() != 2 {
+            return Err(Error::DoCmdError(String::from("args unmatched.")));
         }
-        Ok(())
+        let (from, to) = match (args[0].parse::<usize>(), args[1].parse::<usize>()) {
+            (Ok(from), Ok(to)) if from >= 1 && from <= to => (from, to),
+            _ => return Err(Error::DoCmdError(String::from("args unmatched."))),
+        };
+        if filter.is_empty() {
+            let records = self.store.get_range_records(from, to);
+            return print_ranked_records(printer, (from..).zip(records).collect());
+        }
+        // range in the records which match the filter, but show their ranks in all the records
+        let records = self.store.get_all_records();
+        let music = self
+            .store
+            .get_music_by_id(records.iter().map(|r| r.get_music_id()).collect());
+        let records = (1..)
+            .zip(records)
+            .filter(|(_, r)| {
+                let m = music.iter().find(|m| m.get_id() == r.get_music_id());
+                filter.match_chart(m, r.get_difficulty(), r.get_level())
+            })
+            .skip(from - 1)
+            .take(to - from + 1)
+            .collect();
+        print_ranked_records(printer, records)
+    }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        completion::complete_filter(self.store.as_ref(), args)
+    }
+}
+
+/// one line summary of the music metadata, the unknown ones are omitted.
+fn music_summary(m: &Music) -> String {
+    let mut metadata = Vec::new();
+    if !m.get_artist().is_empty() {
+        metadata.push(format!("artist: {}", m.get_artist()));
+    }
+    if !m.get_bpm().is_empty() {
+        metadata.push(format!("bpm: {}", m.get_bpm()));
+    }
+    if m.get_version() > 0 {
+        metadata.push(format!("version: {}", m.get_version()));
+    }
+    if let Some(date) = m.get_distribution_date() {
+        metadata.push(format!("released: {}", date));
+    }
+    format!(
+        "Music {}: <{}> {}",
+        m.get_id(),
+        m.get_name(),
+        metadata.join(", ")
+    )
+    .trim_end()
+    .to_string()
+}
+
+/// the name of current player to label the exports, empty if unknown.
+fn player_name<T: DataSource>(store: &T) -> String {
+    store.get_profile().map(|p| p.name).unwrap_or_default()
+}
+
+/// format the date of records, `-` if unknown.
+fn format_date(date: Option<DateTime<Local>>) -> String {
+    date.map_or(String::from("-"), |d| {
+        d.format("%Y-%m-%d %H:%M").to_string()
+    })
+}
+
+/// print records with their ranks in volforce order.
+fn print_ranked_records(printer: &Printer, records: Vec<(usize, FullRecord)>) -> Result<()> {
+    #[derive(Serialize)]
+    struct RankedRecord<'a> {
+        rank: usize,
+        #[serde(flatten)]
+        record: &'a FullRecord,
+    }
+
+    if records.is_empty() {
+        return Err(Error::DoCmdError(String::from(
+            "The music record not found.",
+        )));
+    }
+    let mut tab = table!([
+        "rank",
+        "music id",
+        "music name",
+        "difficulty",
+        "level",
+        "score",
+        "grade",
+        "clear type",
+        "volforce"
+    ]);
+    for (rank, rec) in records.iter() {
+        tab.add_row(row![
+            format!("#{}", rank),
+            rec.get_music_id(),
+            rec.get_music_name_str(),
+            rec.get_difficulty(),
+            rec.get_level(),
+            rec.get_score(),
+            rec.get_grade(),
+            rec.get_clear_type(),
+            rec.get_volforce(),
+        ]);
     }
+    let ranked = records
+        .iter()
+        .map(|(rank, record)| RankedRecord {
+            rank: *rank,
+            record,
+        })
+        .collect::<Vec<RankedRecord>>();
+    printer.print(&tab, &ranked)?;
+    printer.info(format!("{} record(s) founded.", records.len()));
+    Ok(())
 }
 
 pub struct CmdVolforce<T: DataSource> {
@@ -155,10 +351,26 @@ impl<T: DataSource> Cmd for CmdVolforce<T> {
         "compute and print your volforce."
     }
 
-    fn do_cmd(&self, _: &[String]) -> Result<()> {
+    fn do_cmd(&self, _: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct VolforceOutput {
+            player: String,
+            volforce: Volfoce,
+        }
+
         let vf = self.store.get_volforce();
-        println!("Your Volforce: {}", vf);
-        Ok(())
+        if printer.format() == OutputFormat::Table {
+            println!("Your Volforce: {}", vf);
+            return Ok(());
+        }
+        let player = player_name(self.store.as_ref());
+        printer.print(
+            &table!(["player", "volforce"], [player, vf]),
+            &VolforceOutput {
+                player,
+                volforce: vf,
+            },
+        )
     }
 }
 
@@ -183,12 +395,19 @@ impl<T: DataSource> Cmd for CmdCount<T> {
         "count the grades of one level(or all)"
     }
 
-    fn do_cmd(&self, args: &[String]) -> Result<()> {
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct LevelStatOutput<'a> {
+            #[serde(flatten)]
+            stat: &'a LevelStat,
+            total: usize,
+        }
+
         if args.len() != 1 {
             return Err(Error::DoCmdError(String::from("args unmatched.")));
         }
         let stats = if let Ok(level) = args[0].as_str().parse::<u8>() {
-            if level < 1 || level > 20 {
+            if !(1..=20).contains(&level) {
                 return Err(Error::DoCmdError(String::from("args unmatched.")));
             }
             self.store.get_level_stat(Some(level))
@@ -198,7 +417,9 @@ impl<T: DataSource> Cmd for CmdCount<T> {
             return Err(Error::DoCmdError(String::from("args unmatched.")));
         };
         let mut tab = table!(["level", "S", "AAA+", "AAA", "PUC", "UC", "HC", "NC", "played"]);
+        let mut output = Vec::new();
         for s in stats.iter() {
+            let total = self.store.get_level_count(*s.level());
             tab.add_row(row![
                 s.level(),
                 s.s_num(),
@@ -208,10 +429,1600 @@ impl<T: DataSource> Cmd for CmdCount<T> {
                 s.uc_num(),
                 s.hc_num(),
                 s.nc_num(),
-                format!("{}/{}", s.played(), self.store.get_level_count(*s.level())),
+                format!("{}/{}", s.played(), total),
+            ]);
+            output.push(LevelStatOutput { stat: s, total });
+        }
+        printer.print(&tab, &output)
+    }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        let levels = completion::levels(self.store.as_ref())
+            .iter()
+            .map(|l| format!("{} ", l))
+            .collect::<Vec<String>>();
+        completion::complete_token(args, std::iter::once(String::from("all ")).chain(levels))
+    }
+}
+
+/// `CmdMusic` is used to get music information from the music database.
+pub struct CmdMusic<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdMusic<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdMusic { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdMusic<T> {
+    fn name(&self) -> &str {
+        "music"
+    }
+    fn usage(&self) -> &str {
+        "music <music-id | music-name | key=value ...>"
+    }
+    fn description(&self) -> &str {
+        "get music information by id, name or metadata."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct MusicChart {
+            music_id: u16,
+            music_name: String,
+            artist: String,
+            bpm: String,
+            genre: u32,
+            version: u8,
+            distribution_date: Option<NaiveDate>,
+            difficulty: Difficulty,
+            level: u8,
+            played: bool,
+        }
+
+        let (filter, args) = ChartFilter::parse(args)?;
+        let music = if args.is_empty() {
+            if filter.is_empty() {
+                return Err(Error::DoCmdError(String::from("args unmatched.")));
+            }
+            self.store.get_all_music()
+        } else if let Ok(music_id) = args[0].as_str().parse::<u16>() {
+            self.store.get_music_by_id(vec![music_id])
+        } else {
+            self.store.get_music_by_name(args.join(" "))
+        };
+        let records = self
+            .store
+            .get_record_by_id(music.iter().map(|m| m.get_id()).collect());
+        let mut tab = table!([
+            "music id",
+            "music name",
+            "artist",
+            "bpm",
+            "version",
+            "released",
+            "difficulty",
+            "level",
+            "played"
+        ]);
+        let mut charts = Vec::new();
+        let mut music_num = 0;
+        for m in music.iter().filter(|m| filter.match_music(Some(m))) {
+            let mut matched = false;
+            for (difficulty, level) in m.get_charts() {
+                if !filter.match_chart(Some(m), difficulty, level) {
+                    continue;
+                }
+                matched = true;
+                let played = records
+                    .iter()
+                    .any(|r| r.get_music_id() == m.get_id() && r.get_difficulty() == difficulty);
+                let distribution_date = m.get_distribution_date();
+                tab.add_row(row![
+                    m.get_id(),
+                    m.get_name(),
+                    m.get_artist(),
+                    m.get_bpm(),
+                    m.get_version(),
+                    distribution_date.map_or(String::from("-"), |d| d.to_string()),
+                    difficulty,
+                    level,
+                    if played { "Yes" } else { "No" },
+                ]);
+                charts.push(MusicChart {
+                    music_id: m.get_id(),
+                    music_name: m.get_name(),
+                    artist: m.get_artist(),
+                    bpm: m.get_bpm(),
+                    genre: m.get_genre(),
+                    version: m.get_version(),
+                    distribution_date,
+                    difficulty,
+                    level,
+                    played,
+                });
+            }
+            if matched {
+                music_num += 1;
+            }
+        }
+        if charts.is_empty() {
+            return Err(Error::DoCmdError(String::from("The music not found.")));
+        }
+        printer.print(&tab, &charts)?;
+        printer.info(format!("{} music founded.", music_num));
+        Ok(())
+    }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        // complete the filters if there are any, or the music titles and filter keys.
+        if args.contains('=') {
+            return completion::complete_filter(self.store.as_ref(), args);
+        }
+        let (start, mut candidates) = completion::complete_music(self.store.as_ref(), args);
+        if !args.trim().contains(char::is_whitespace) {
+            let (_, keys) = completion::complete_filter(self.store.as_ref(), args.trim_start());
+            candidates.extend(keys);
+        }
+        (start, candidates)
+    }
+}
+
+/// `CmdTarget` is used to compute the scores needed on a chart to raise volforce.
+pub struct CmdTarget<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdTarget<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdTarget { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdTarget<T> {
+    fn name(&self) -> &str {
+        "target"
+    }
+    fn usage(&self) -> &str {
+        "target <music-id | music-name> <difficulty> [vf-gain]"
+    }
+    fn description(&self) -> &str {
+        "get the scores needed to enter best 50, beat the record or raise volforce."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct TargetRow {
+            goal: String,
+            clear_type: Option<ClearType>,
+            /// the minimum score needed, `None` if impossible or reached already.
+            score: Option<u32>,
+            grade: Option<Grade>,
+            /// the volforce of the chart with the score.
+            volforce: Option<Volfoce>,
+        }
+
+        // the args are like: <music...> <difficulty> [vf-gain]
+        let (args, gain) = match args.split_last() {
+            Some((last, rest))
+                if rest.len() >= 2 && rest[rest.len() - 1].parse::<Difficulty>().is_ok() =>
+            {
+                match last.parse::<f64>() {
+                    Ok(gain) if gain.is_finite() && gain > 0.0 => (rest, Some(gain)),
+                    _ => return Err(Error::DoCmdError(String::from("args unmatched."))),
+                }
+            }
+            _ => (args, None),
+        };
+        let (difficulty, music_args) = match args.split_last() {
+            Some((d, rest)) if !rest.is_empty() => match d.parse::<Difficulty>() {
+                Ok(d) => (d, rest),
+                Err(_) => return Err(Error::DoCmdError(String::from("args unmatched."))),
+            },
+            _ => return Err(Error::DoCmdError(String::from("args unmatched."))),
+        };
+        let music = if let Ok(music_id) = music_args[0].as_str().parse::<u16>() {
+            self.store.get_music_by_id(vec![music_id])
+        } else {
+            self.store.get_music_by_name(music_args.join(" "))
+        };
+        let music = match music.into_iter().next() {
+            Some(m) => m,
+            None => return Err(Error::DoCmdError(String::from("The music not found."))),
+        };
+        // the infinite slot is resolved by the music, `inf` means any of them.
+        let difficulty = if difficulty.is_infinite_slot() {
+            difficulty.inf_ver(music.get_inf_ver())
+        } else {
+            difficulty
+        };
+        let level = music.get_level(difficulty);
+        if level == 0 {
+            return Err(Error::DoCmdError(format!(
+                "The music has no {} chart.",
+                difficulty
+            )));
+        }
+
+        let rule = self.store.get_volforce_rule();
+        let best50 = self.store.get_best50_records();
+        let is_chart =
+            |r: &FullRecord| r.get_music_id() == music.get_id() && r.get_difficulty() == difficulty;
+        let current = self
+            .store
+            .get_record_by_id(vec![music.get_id()])
+            .into_iter()
+            .find(|r| is_chart(r));
+        let rank = best50.iter().position(is_chart);
+        // the records which stay in best 50 whatever the chart scores
+        let mut others = best50
+            .iter()
+            .filter(|r| !is_chart(r))
+            .map(|r| r.get_volforce().get_internal())
+            .collect::<Vec<u32>>();
+        let lowest = if others.len() >= 50 {
+            others.pop()
+        } else {
+            None
+        };
+
+        printer.info(format!(
+            "Target: <{}> {} {}",
+            music.get_name(),
+            difficulty,
+            level
+        ));
+        printer.info(match (&current, rank) {
+            (Some(r), Some(rank)) => format!(
+                "Current: {} {} {} {}, #{} in best 50",
+                r.get_score(),
+                r.get_grade(),
+                r.get_clear_type(),
+                r.get_volforce(),
+                rank + 1
+            ),
+            (Some(r), None) => format!(
+                "Current: {} {} {} {}, not in best 50",
+                r.get_score(),
+                r.get_grade(),
+                r.get_clear_type(),
+                r.get_volforce()
+            ),
+            (None, _) => String::from("Current: not played"),
+        });
+
+        // the goals and the minimum chart volforce to reach them, `None` if reached already.
+        let mut goals = vec![
+            (
+                String::from("enter best 50"),
+                match rank {
+                    Some(_) => None,
+                    None => Some(lowest.map_or(1, |vf| vf + 1)),
+                },
+            ),
+            (
+                String::from("beat the record"),
+                Some(
+                    current
+                        .as_ref()
+                        .map_or(1, |r| r.get_volforce().get_internal() + 1),
+                ),
+            ),
+        ];
+        if let Some(gain) = gain {
+            let target = match rule.target_sum(self.store.get_volforce(), gain) {
+                Some(target) => target,
+                None => {
+                    return Err(Error::DoCmdError(format!(
+                        "volforce can not be raised by {}, the max is {}.",
+                        gain,
+                        rule.max_volforce()
+                    )))
+                }
+            };
+            let sum: u32 = others.iter().sum();
+            goals.push((
+                format!("raise volforce by {}", gain),
+                Some(target.saturating_sub(sum).max(1)),
+            ));
+        }
+
+        let mut tab = table!(["goal", "clear type", "score", "grade", "volforce"]);
+        let mut rows = Vec::new();
+        for (goal, vf) in goals {
+            let vf = match vf {
+                Some(vf) => Volfoce::new(vf, rule.precision()),
+                None => {
+                    tab.add_row(row![goal, "-", "reached", "-", "-"]);
+                    rows.push(TargetRow {
+                        goal,
+                        clear_type: None,
+                        score: None,
+                        grade: None,
+                        volforce: None,
+                    });
+                    continue;
+                }
+            };
+            for clear in rule.clear_types() {
+                let score = rule.min_score(level, clear, vf);
+                let grade = score.map(Grade::from_score);
+                let volforce =
+                    score.map(|s| rule.compute_volforce(level, s, Grade::from_score(s), clear));
+                tab.add_row(row![
+                    goal,
+                    clear,
+                    score.map_or(String::from("impossible"), |s| s.to_string()),
+                    grade.map_or(String::from("-"), |g| g.to_string()),
+                    volforce.map_or(String::from("-"), |v| v.to_string()),
+                ]);
+                rows.push(TargetRow {
+                    goal: goal.clone(),
+                    clear_type: Some(clear),
+                    score,
+                    grade,
+                    volforce,
+                });
+            }
+        }
+        printer.print(&tab, &rows)
+    }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        // complete the music first, and then the difficulty
+        let (start, candidates) = completion::complete_music(self.store.as_ref(), args);
+        if !candidates.is_empty() || !args.trim().contains(char::is_whitespace) {
+            return (start, candidates);
+        }
+        completion::complete_token(args, Difficulty::ALL.iter().map(|d| format!("{} ", d)))
+    }
+}
+
+/// `CmdSuggest` is used to find the charts which may raise volforce the most.
+pub struct CmdSuggest<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdSuggest<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdSuggest { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdSuggest<T> {
+    fn name(&self) -> &str {
+        "suggest"
+    }
+    fn usage(&self) -> &str {
+        "suggest [count] [key=value ...]"
+    }
+    fn description(&self) -> &str {
+        "suggest the charts to play by the estimated volforce gain."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct Suggestion {
+            music_id: u16,
+            music_name: String,
+            difficulty: Difficulty,
+            level: u8,
+            /// the current record, `None` if not played.
+            score: Option<u32>,
+            clear_type: Option<ClearType>,
+            /// the score and clear type expected by the typical ones of the level.
+            target_score: u32,
+            target_clear_type: ClearType,
+            volforce: Volfoce,
+            /// the estimated gain of the player's volforce.
+            gain: Volfoce,
+        }
+
+        let (filter, args) = ChartFilter::parse(args)?;
+        let count = match args.as_slice() {
+            [] => 20,
+            [n] => match n.parse::<usize>() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(Error::DoCmdError(String::from("args unmatched."))),
+            },
+            _ => return Err(Error::DoCmdError(String::from("args unmatched."))),
+        };
+
+        let rule = self.store.get_volforce_rule();
+        let records = self.store.get_all_records();
+        let typicals = typical_plays(&records);
+        let best50 = records
+            .iter()
+            .take(50)
+            .map(|r| r.get_volforce().get_internal())
+            .collect::<Vec<u32>>();
+        // the volforce to beat to enter best 50
+        let lowest = if best50.len() >= 50 {
+            best50.last().copied().unwrap_or(0)
+        } else {
+            0
+        };
+
+        let mut suggestions = Vec::new();
+        for m in self.store.get_all_music() {
+            for (difficulty, level) in m.get_charts() {
+                let (score, clear) = match typicals.get(&level) {
+                    Some(&typical) => typical,
+                    // the levels not cleared yet are not suggested
+                    None => continue,
+                };
+                if !filter.match_chart(Some(&m), difficulty, level) {
+                    continue;
+                }
+                let current = records.iter().position(|r| {
+                    r.get_music_id() == m.get_id() && r.get_difficulty() == difficulty
+                });
+                let (score, clear) = match current.map(|i| &records[i]) {
+                    Some(r) => (
+                        score.max(r.get_score()),
+                        if clear > r.get_clear_type() {
+                            clear
+                        } else {
+                            r.get_clear_type()
+                        },
+                    ),
+                    None => (score, clear),
+                };
+                let vf = rule.compute_volforce(level, score, Grade::from_score(score), clear);
+                // the gain of the sum of best 50
+                let gain = match current {
+                    Some(i) if i < 50 => vf
+                        .get_internal()
+                        .saturating_sub(records[i].get_volforce().get_internal()),
+                    _ => vf.get_internal().saturating_sub(lowest),
+                };
+                if gain == 0 {
+                    continue;
+                }
+                let current = current.map(|i| &records[i]);
+                suggestions.push(Suggestion {
+                    music_id: m.get_id(),
+                    music_name: m.get_name(),
+                    difficulty,
+                    level,
+                    score: current.map(|r| r.get_score()),
+                    clear_type: current.map(|r| r.get_clear_type()),
+                    target_score: score,
+                    target_clear_type: clear,
+                    volforce: vf,
+                    gain: Volfoce::new(gain / 50, rule.precision()),
+                });
+            }
+        }
+        if suggestions.is_empty() {
+            return Err(Error::DoCmdError(String::from("No chart to suggest.")));
+        }
+        suggestions.sort_by(|a, b| {
+            b.gain
+                .cmp(&a.gain)
+                .then(b.volforce.cmp(&a.volforce))
+                .then(a.music_id.cmp(&b.music_id))
+        });
+        suggestions.truncate(count);
+
+        let mut tab = table!([
+            "music id",
+            "music name",
+            "difficulty",
+            "level",
+            "score",
+            "clear type",
+            "target score",
+            "target clear type",
+            "volforce",
+            "gain"
+        ]);
+        for s in suggestions.iter() {
+            tab.add_row(row![
+                s.music_id,
+                s.music_name,
+                s.difficulty,
+                s.level,
+                s.score.map_or(String::from("-"), |s| s.to_string()),
+                s.clear_type.map_or(String::from("-"), |c| c.to_string()),
+                s.target_score,
+                s.target_clear_type,
+                s.volforce,
+                format!("+{}", s.gain),
+            ]);
+        }
+        printer.print(&tab, &suggestions)?;
+        printer.info(format!("{} chart(s) suggested.", suggestions.len()));
+        Ok(())
+    }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        completion::complete_filter(self.store.as_ref(), args)
+    }
+}
+
+/// the typical (median) score and clear type of each level which has been cleared.
+fn typical_plays(records: &[FullRecord]) -> HashMap<u8, (u32, ClearType)> {
+    let mut plays: HashMap<u8, Vec<&FullRecord>> = HashMap::new();
+    for r in records.iter() {
+        plays.entry(r.get_level()).or_default().push(r);
+    }
+    plays
+        .into_iter()
+        .filter_map(|(level, recs)| {
+            let mut scores = recs.iter().map(|r| r.get_score()).collect::<Vec<u32>>();
+            let mut clears = recs
+                .iter()
+                .map(|r| r.get_clear_type())
+                .filter(|&c| c >= ClearType::Complete)
+                .collect::<Vec<ClearType>>();
+            if clears.is_empty() {
+                return None;
+            }
+            scores.sort_unstable();
+            clears.sort_unstable();
+            Some((level, (scores[scores.len() / 2], clears[clears.len() / 2])))
+        })
+        .collect()
+}
+
+/// `CmdFolder` is used to list all the charts of a level, like the level folders of the game.
+pub struct CmdFolder<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdFolder<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdFolder { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdFolder<T> {
+    fn name(&self) -> &str {
+        "folder"
+    }
+    fn usage(&self) -> &str {
+        "folder <level> [score | lamp]"
+    }
+    fn description(&self) -> &str {
+        "list all the charts of one level with your records."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct FolderChart {
+            music_id: u16,
+            music_name: String,
+            difficulty: Difficulty,
+            level: u8,
+            /// the record of the chart, `None` if not played.
+            score: Option<u32>,
+            grade: Option<Grade>,
+            clear_type: Option<ClearType>,
+            volforce: Option<Volfoce>,
+        }
+
+        let level = match args.first().map(|l| l.parse::<u8>()) {
+            Some(Ok(level)) if (1..=20).contains(&level) && args.len() <= 2 => level,
+            _ => return Err(Error::DoCmdError(String::from("args unmatched."))),
+        };
+        let music = self
+            .store
+            .get_all_music()
+            .into_iter()
+            .filter(|m| m.has_level(level))
+            .collect::<Vec<Music>>();
+        let records = self
+            .store
+            .get_record_by_id(music.iter().map(|m| m.get_id()).collect());
+        let mut charts = Vec::new();
+        for m in music.iter() {
+            for (difficulty, _) in m.get_charts().into_iter().filter(|(_, l)| *l == level) {
+                let rec = records
+                    .iter()
+                    .find(|r| r.get_music_id() == m.get_id() && r.get_difficulty() == difficulty);
+                charts.push(FolderChart {
+                    music_id: m.get_id(),
+                    music_name: m.get_name(),
+                    difficulty,
+                    level,
+                    score: rec.map(|r| r.get_score()),
+                    grade: rec.map(|r| r.get_grade()),
+                    clear_type: rec.map(|r| r.get_clear_type()),
+                    volforce: rec.map(|r| r.get_volforce()),
+                });
+            }
+        }
+        // sorted in descending order, and the charts not played are the last.
+        match args.get(1).map(String::as_str) {
+            None => {}
+            Some("score") => charts.sort_by_key(|c| Reverse(c.score)),
+            Some("lamp") => charts.sort_by(|a, b| {
+                let lamp = |c: &FolderChart| c.clear_type.map(Into::<u8>::into);
+                lamp(b).cmp(&lamp(a)).then(b.score.cmp(&a.score))
+            }),
+            Some(_) => return Err(Error::DoCmdError(String::from("args unmatched."))),
+        }
+
+        let mut tab = table!([
+            "music id",
+            "music name",
+            "difficulty",
+            "score",
+            "grade",
+            "clear type",
+            "volforce"
+        ]);
+        for c in charts.iter() {
+            tab.add_row(row![
+                c.music_id,
+                c.music_name,
+                c.difficulty,
+                c.score.map_or(String::from("-"), |s| s.to_string()),
+                c.grade.map_or(String::from("-"), |g| g.to_string()),
+                c.clear_type
+                    .map_or(String::from("No Play"), |c| c.to_string()),
+                c.volforce.map_or(String::from("-"), |v| v.to_string()),
+            ]);
+        }
+        printer.print(&tab, &charts)?;
+        printer.info(format!(
+            "{}/{} chart(s) played.",
+            charts.iter().filter(|c| c.score.is_some()).count(),
+            charts.len()
+        ));
+        Ok(())
+    }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        if args.trim().contains(char::is_whitespace) {
+            return completion::complete_token(
+                args,
+                ["score ", "lamp "].iter().map(|s| s.to_string()),
+            );
+        }
+        let levels = completion::levels(self.store.as_ref())
+            .iter()
+            .map(|l| format!("{} ", l))
+            .collect::<Vec<String>>();
+        completion::complete_token(args, levels)
+    }
+}
+
+/// `CmdSnapshot` is used to save all the records to a file.
+pub struct CmdSnapshot<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdSnapshot<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdSnapshot { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdSnapshot<T> {
+    fn name(&self) -> &str {
+        "snapshot"
+    }
+    fn usage(&self) -> &str {
+        "snapshot <path>"
+    }
+    fn description(&self) -> &str {
+        "save all your records to a snapshot file."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        if args.len() != 1 {
+            return Err(Error::DoCmdError(String::from("args unmatched.")));
+        }
+        let player = player_name(self.store.as_ref());
+        let snapshot = Snapshot::new(
+            player,
+            self.store.get_all_records(),
+            self.store.get_volforce_rule(),
+        );
+        snapshot.save(&args[0])?;
+        printer.info(format!(
+            "{} record(s) of {} saved to {}.",
+            snapshot.records.len(),
+            if snapshot.player.is_empty() {
+                "you"
+            } else {
+                snapshot.player.as_str()
+            },
+            args[0]
+        ));
+        Ok(())
+    }
+}
+
+/// `CmdDiff` is used to compare the current records with a snapshot.
+pub struct CmdDiff<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdDiff<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdDiff { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdDiff<T> {
+    fn name(&self) -> &str {
+        "diff"
+    }
+    fn usage(&self) -> &str {
+        "diff <snapshot>"
+    }
+    fn description(&self) -> &str {
+        "compare your records with a snapshot."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct Change<'a> {
+            /// the kinds of the change: new, score, lamp or grade.
+            change: Vec<&'static str>,
+            /// the record in the snapshot, `None` if it is new.
+            before: Option<&'a FullRecord>,
+            after: &'a FullRecord,
+        }
+        #[derive(Serialize)]
+        struct DiffOutput<'a> {
+            player: &'a str,
+            snapshot_created_at: DateTime<Local>,
+            volforce_before: Volfoce,
+            volforce_after: Volfoce,
+            changes: Vec<Change<'a>>,
+        }
+
+        if args.len() != 1 {
+            return Err(Error::DoCmdError(String::from("args unmatched.")));
+        }
+        let rule = self.store.get_volforce_rule();
+        let snapshot = Snapshot::open(&args[0], rule)?;
+        let records = self.store.get_all_records();
+        let mut changes = Vec::new();
+        for r in records.iter() {
+            let before = snapshot.get_record(r.get_music_id(), r.get_difficulty());
+            let mut change = Vec::new();
+            match before {
+                None => change.push("new"),
+                Some(b) => {
+                    if r.get_score() > b.get_score() {
+                        change.push("score");
+                    }
+                    if r.get_clear_type() > b.get_clear_type() {
+                        change.push("lamp");
+                    }
+                    if Into::<u8>::into(r.get_grade()) > b.get_grade().into() {
+                        change.push("grade");
+                    }
+                }
+            }
+            if !change.is_empty() {
+                changes.push(Change {
+                    change,
+                    before,
+                    after: r,
+                });
+            }
+        }
+
+        let mut tab = table!([
+            "music id",
+            "music name",
+            "difficulty",
+            "level",
+            "change",
+            "score",
+            "grade",
+            "clear type",
+            "volforce"
+        ]);
+        // show as `before -> after`
+        let arrow = |before: Option<String>, after: String| match before {
+            Some(b) if b != after => format!("{} -> {}", b, after),
+            _ => after,
+        };
+        for c in changes.iter() {
+            let (b, a) = (c.before, c.after);
+            tab.add_row(row![
+                a.get_music_id(),
+                a.get_music_name_str(),
+                a.get_difficulty(),
+                a.get_level(),
+                c.change.join(", "),
+                match b {
+                    Some(b) => format!(
+                        "{} -> {} (+{})",
+                        b.get_score(),
+                        a.get_score(),
+                        a.get_score().saturating_sub(b.get_score())
+                    ),
+                    None => a.get_score().to_string(),
+                },
+                arrow(
+                    b.map(|b| b.get_grade().to_string()),
+                    a.get_grade().to_string()
+                ),
+                arrow(
+                    b.map(|b| b.get_clear_type().to_string()),
+                    a.get_clear_type().to_string()
+                ),
+                arrow(
+                    b.map(|b| b.get_volforce().to_string()),
+                    a.get_volforce().to_string()
+                ),
+            ]);
+        }
+        let volforce_after = rule.total_volforce(&records[..records.len().min(50)]);
+        let output = DiffOutput {
+            player: &snapshot.player,
+            snapshot_created_at: snapshot.created_at,
+            volforce_before: snapshot.volforce,
+            volforce_after,
+            changes,
+        };
+        printer.print(&tab, &output)?;
+        printer.info(format!(
+            "{} chart(s) changed since {}.",
+            output.changes.len(),
+            format_date(Some(snapshot.created_at))
+        ));
+        let (before, after) = (
+            snapshot.volforce.get_internal(),
+            volforce_after.get_internal(),
+        );
+        printer.info(format!(
+            "Volforce: {} -> {} ({}{})",
+            snapshot.volforce,
+            volforce_after,
+            if after >= before { "+" } else { "-" },
+            Volfoce::new(after.abs_diff(before), rule.precision())
+        ));
+        Ok(())
+    }
+}
+
+/// `CmdRecent` is used to get the records updated recently.
+pub struct CmdRecent<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdRecent<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdRecent { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdRecent<T> {
+    fn name(&self) -> &str {
+        "recent"
+    }
+    fn usage(&self) -> &str {
+        "recent [count | since <date>]"
+    }
+    fn description(&self) -> &str {
+        "get the records updated recently."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        let mut records = self
+            .store
+            .get_all_records()
+            .into_iter()
+            .filter(|r| r.get_updated_at().is_some())
+            .collect::<Vec<FullRecord>>();
+        records.sort_by_key(|r| Reverse(r.get_updated_at()));
+        let records = match args {
+            [] => records.into_iter().take(20).collect(),
+            [n] => match n.parse::<usize>() {
+                Ok(n) if n > 0 => records.into_iter().take(n).collect(),
+                _ => return Err(Error::DoCmdError(String::from("args unmatched."))),
+            },
+            [since, date @ ..] if since == "since" && !date.is_empty() => {
+                let since = parse_date(&date.join(" "))?;
+                records
+                    .into_iter()
+                    .filter(|r| r.get_updated_at().is_some_and(|d| d >= since))
+                    .collect::<Vec<FullRecord>>()
+            }
+            _ => return Err(Error::DoCmdError(String::from("args unmatched."))),
+        };
+        if records.is_empty() {
+            return Err(Error::DoCmdError(String::from(
+                "The music record not found.",
+            )));
+        }
+
+        let mut tab = table!([
+            "last updated",
+            "music id",
+            "music name",
+            "difficulty",
+            "level",
+            "score",
+            "grade",
+            "clear type",
+            "volforce",
+            "first played"
+        ]);
+        for rec in records.iter() {
+            tab.add_row(row![
+                format_date(rec.get_updated_at()),
+                rec.get_music_id(),
+                rec.get_music_name_str(),
+                rec.get_difficulty(),
+                rec.get_level(),
+                rec.get_score(),
+                rec.get_grade(),
+                rec.get_clear_type(),
+                rec.get_volforce(),
+                format_date(rec.get_created_at()),
             ]);
         }
+        printer.print(&tab, &records)?;
+        printer.info(format!("{} record(s) founded.", records.len()));
+        Ok(())
+    }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        completion::complete_token(args, std::iter::once(String::from("since ")))
+    }
+}
+
+/// parse a local date like `2022-06-01` or `2022-06-01 20:30`.
+fn parse_date(date: &str) -> Result<DateTime<Local>> {
+    if let Ok(d) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Ok(start_of_day(d));
+    }
+    NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M")
+        .ok()
+        .and_then(|d| Local.from_local_datetime(&d).earliest())
+        .ok_or_else(|| Error::DoCmdError(format!("invalid date: {}", date)))
+}
+
+/// the start of the local day, which is later than midnight if midnight is skipped by DST.
+fn start_of_day(date: NaiveDate) -> DateTime<Local> {
+    (0..24)
+        .filter_map(|h| date.and_hms_opt(h, 0, 0))
+        .find_map(|d| Local.from_local_datetime(&d).earliest())
+        .unwrap_or_else(|| Local.from_utc_datetime(&date.and_time(NaiveTime::MIN)))
+}
+
+/// the period of `vfhistory`.
+#[derive(Clone, Copy)]
+enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    /// the first day of the period which contains `date`.
+    fn start_of(&self, date: NaiveDate) -> NaiveDate {
+        match *self {
+            Period::Day => date,
+            Period::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Period::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// the first day of the next period, `start` is the first day of a period.
+    fn next(&self, start: NaiveDate) -> NaiveDate {
+        match *self {
+            Period::Day => start + Duration::days(1),
+            Period::Week => start + Duration::days(7),
+            Period::Month => start
+                .checked_add_months(Months::new(1))
+                .unwrap_or(start + Duration::days(31)),
+        }
+    }
+
+    fn format(&self, start: NaiveDate) -> String {
+        match *self {
+            Period::Month => start.format("%Y-%m").to_string(),
+            _ => start.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+/// `CmdVolforceHistory` is used to rebuild the volforce in the past by the record timestamps.
+pub struct CmdVolforceHistory<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdVolforceHistory<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdVolforceHistory { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdVolforceHistory<T> {
+    fn name(&self) -> &str {
+        "vfhistory"
+    }
+    fn usage(&self) -> &str {
+        "vfhistory [day | week | month] [csv-path]"
+    }
+    fn description(&self) -> &str {
+        "get your volforce at the end of each period, and export it."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct VolforcePoint<'a> {
+            player: &'a str,
+            period: String,
+            volforce: Volfoce,
+            /// the change from the previous period.
+            change: Volfoce,
+            /// the number of records updated in the period.
+            updated: usize,
+        }
+
+        let parse_period = |arg: &str| match arg {
+            "day" => Some(Period::Day),
+            "week" => Some(Period::Week),
+            "month" => Some(Period::Month),
+            _ => None,
+        };
+        // the period is weekly by default, so a single arg may be the csv path
+        let (period, path) = match args {
+            [] => (Period::Week, None),
+            [arg] => match parse_period(arg) {
+                Some(period) => (period, None),
+                None => (Period::Week, Some(arg)),
+            },
+            [period, path] => match parse_period(period) {
+                Some(period) => (period, Some(path)),
+                None => return Err(Error::DoCmdError(String::from("args unmatched."))),
+            },
+            _ => return Err(Error::DoCmdError(String::from("args unmatched."))),
+        };
+        let records = self.store.get_all_records();
+        // the records without timestamps are not in the history
+        let untimed = records
+            .iter()
+            .filter(|r| r.get_updated_at().is_none())
+            .count();
+        let updated = records
+            .iter()
+            .filter_map(|r| r.get_updated_at())
+            .map(|d| d.date_naive())
+            .collect::<Vec<NaiveDate>>();
+        let first = match updated.iter().min() {
+            Some(&first) => period.start_of(first),
+            None => {
+                return Err(Error::DoCmdError(String::from(
+                    "No record with timestamps.",
+                )))
+            }
+        };
+        let today = Local::now().date_naive();
+        let mut starts = vec![first];
+        while let Some(&start) = starts.last().filter(|&&s| s <= today) {
+            starts.push(period.next(start));
+        }
+        // (start, end) of the periods, the end is the start of the next period
+        let periods = starts
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .collect::<Vec<(NaiveDate, NaiveDate)>>();
+        // the volforce at the end of a period is the one at the start of the next period
+        let ends = periods
+            .iter()
+            .map(|&(_, end)| start_of_day(end))
+            .collect::<Vec<DateTime<Local>>>();
+        let history = self.store.get_volforce_history(&ends);
+
+        // the player is labeled in the exports, but not in the table for humans
+        let player = player_name(self.store.as_ref());
+        let with_player = path.is_some() || printer.format() != OutputFormat::Table;
+        let mut header = row!["period", "volforce", "change", "updated"];
+        if with_player {
+            header.insert_cell(0, Cell::new("player"));
+        }
+        let mut tab = Table::new();
+        tab.add_row(header);
+        let mut points: Vec<VolforcePoint> = Vec::new();
+        for (vf, &(start, end)) in history.into_iter().zip(periods.iter()) {
+            let before = points.last().map_or(0, |p| p.volforce.get_internal());
+            let point = VolforcePoint {
+                player: &player,
+                period: period.format(start),
+                volforce: vf,
+                change: Volfoce::new(vf.get_internal().saturating_sub(before), vf.get_precision()),
+                updated: updated.iter().filter(|&&d| d >= start && d < end).count(),
+            };
+            let mut row = row![
+                point.period,
+                point.volforce,
+                format!("+{}", point.change),
+                point.updated,
+            ];
+            if with_player {
+                row.insert_cell(0, Cell::new(&player));
+            }
+            tab.add_row(row);
+            points.push(point);
+        }
+        if let Some(path) = path {
+            let mut writer = csv::Writer::from_path(path)?;
+            for p in points.iter() {
+                writer.serialize(p)?;
+            }
+            writer.flush()?;
+            printer.info(format!("{} period(s) exported to {}.", points.len(), path));
+        } else {
+            printer.print(&tab, &points)?;
+        }
+        if untimed > 0 {
+            printer.info(format!(
+                "{} record(s) without timestamps are not counted.",
+                untimed
+            ));
+        }
+        Ok(())
+    }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        completion::complete_token(
+            args,
+            ["day ", "week ", "month "].iter().map(|s| s.to_string()),
+        )
+    }
+}
+
+/// `CmdRival` is used to compare your records with a rival's.
+pub struct CmdRival<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdRival<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdRival { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdRival<T> {
+    fn name(&self) -> &str {
+        "rival"
+    }
+    fn usage(&self) -> &str {
+        "rival <name>"
+    }
+    fn description(&self) -> &str {
+        "compare your records with a rival's, chart by chart."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        #[derive(Serialize)]
+        struct ChartResult<'a> {
+            /// WIN, LOSE or DRAW, from your side.
+            result: &'static str,
+            mine: Option<&'a FullRecord>,
+            rival: Option<&'a FullRecord>,
+        }
+        #[derive(Serialize)]
+        struct LevelResult {
+            level: u8,
+            win: usize,
+            lose: usize,
+            draw: usize,
+        }
+        #[derive(Serialize)]
+        struct Best50Diff<'a> {
+            /// whose best 50 the chart is in only: mine or rival.
+            only_in: &'static str,
+            rank: usize,
+            record: &'a FullRecord,
+        }
+        #[derive(Serialize)]
+        struct RivalOutput<'a> {
+            player: String,
+            rival: &'a str,
+            volforce: Volfoce,
+            rival_volforce: Volfoce,
+            charts: Vec<ChartResult<'a>>,
+            levels: Vec<LevelResult>,
+            best50: Vec<Best50Diff<'a>>,
+        }
+
+        if args.len() != 1 {
+            return Err(Error::DoCmdError(String::from("args unmatched.")));
+        }
+        let rule = self.store.get_volforce_rule();
+        let mine = self.store.get_all_records();
+        let rival = self.store.get_rival_records(&args[0])?;
+        let chart = |r: &FullRecord| (r.get_music_id(), r.get_difficulty());
+
+        let mut charts = Vec::new();
+        for m in mine.iter() {
+            let r = rival.iter().find(|r| chart(r) == chart(m));
+            charts.push((Some(m), r));
+        }
+        for r in rival.iter() {
+            if !mine.iter().any(|m| chart(m) == chart(r)) {
+                charts.push((None, Some(r)));
+            }
+        }
+        let mut charts = charts
+            .into_iter()
+            .map(|(m, r)| {
+                // compare the score first, then the lamp
+                let key = |rec: Option<&FullRecord>| {
+                    rec.map_or((0, ClearType::None), |rec| {
+                        (rec.get_score(), rec.get_clear_type())
+                    })
+                };
+                let result = match key(m).cmp(&key(r)) {
+                    Ordering::Greater => "WIN",
+                    Ordering::Less => "LOSE",
+                    Ordering::Equal => "DRAW",
+                };
+                ChartResult {
+                    result,
+                    mine: m,
+                    rival: r,
+                }
+            })
+            .collect::<Vec<ChartResult>>();
+        charts.sort_by_key(|c| {
+            let rec = c.mine.or(c.rival).unwrap();
+            (rec.get_music_id(), rec.get_difficulty().to_music_type())
+        });
+
+        let mut levels: Vec<LevelResult> = Vec::new();
+        for c in charts.iter() {
+            let level = c.mine.or(c.rival).unwrap().get_level();
+            let idx = match levels.iter().position(|l| l.level == level) {
+                Some(idx) => idx,
+                None => {
+                    levels.push(LevelResult {
+                        level,
+                        win: 0,
+                        lose: 0,
+                        draw: 0,
+                    });
+                    levels.len() - 1
+                }
+            };
+            match c.result {
+                "WIN" => levels[idx].win += 1,
+                "LOSE" => levels[idx].lose += 1,
+                _ => levels[idx].draw += 1,
+            }
+        }
+        levels.sort_by_key(|l| Reverse(l.level));
+
+        let (my_best50, rival_best50) =
+            (&mine[..mine.len().min(50)], &rival[..rival.len().min(50)]);
+        let mut best50 = Vec::new();
+        for (only_in, this, other) in [
+            ("mine", my_best50, rival_best50),
+            ("rival", rival_best50, my_best50),
+        ] {
+            for (i, rec) in this.iter().enumerate() {
+                if !other.iter().any(|o| chart(o) == chart(rec)) {
+                    best50.push(Best50Diff {
+                        only_in,
+                        rank: i + 1,
+                        record: rec,
+                    });
+                }
+            }
+        }
+
+        let mut tab = table!([
+            "music id",
+            "music name",
+            "difficulty",
+            "level",
+            "score",
+            "rival score",
+            "diff",
+            "clear type",
+            "rival clear type",
+            "result"
+        ]);
+        for c in charts.iter() {
+            let rec = c.mine.or(c.rival).unwrap();
+            let (score, rival_score) = (
+                c.mine.map_or(0, |m| m.get_score()),
+                c.rival.map_or(0, |r| r.get_score()),
+            );
+            let lamp = |rec: Option<&FullRecord>| {
+                rec.map_or(String::from("-"), |rec| rec.get_clear_type().to_string())
+            };
+            tab.add_row(row![
+                rec.get_music_id(),
+                rec.get_music_name_str(),
+                rec.get_difficulty(),
+                rec.get_level(),
+                c.mine
+                    .map_or(String::from("-"), |m| m.get_score().to_string()),
+                c.rival
+                    .map_or(String::from("-"), |r| r.get_score().to_string()),
+                if score >= rival_score {
+                    format!("+{}", score - rival_score)
+                } else {
+                    format!("-{}", rival_score - score)
+                },
+                lamp(c.mine),
+                lamp(c.rival),
+                c.result,
+            ]);
+        }
+        let output = RivalOutput {
+            player: player_name(self.store.as_ref()),
+            rival: &args[0],
+            volforce: rule.total_volforce(my_best50),
+            rival_volforce: rule.total_volforce(rival_best50),
+            charts,
+            levels,
+            best50,
+        };
+        printer.print(&tab, &output)?;
+        if printer.format() != OutputFormat::Table {
+            return Ok(());
+        }
+
+        let mut tab = table!(["level", "win", "lose", "draw"]);
+        for l in output.levels.iter() {
+            tab.add_row(row![l.level, l.win, l.lose, l.draw]);
+        }
         tab.printstd();
+        if !output.best50.is_empty() {
+            let mut tab = table!([
+                "only in",
+                "rank",
+                "music id",
+                "music name",
+                "difficulty",
+                "level",
+                "score",
+                "volforce"
+            ]);
+            for d in output.best50.iter() {
+                tab.add_row(row![
+                    d.only_in,
+                    d.rank,
+                    d.record.get_music_id(),
+                    d.record.get_music_name_str(),
+                    d.record.get_difficulty(),
+                    d.record.get_level(),
+                    d.record.get_score(),
+                    d.record.get_volforce(),
+                ]);
+            }
+            tab.printstd();
+        }
+        let count = |result| output.charts.iter().filter(|c| c.result == result).count();
+        printer.info(format!(
+            "WIN {} / LOSE {} / DRAW {}",
+            count("WIN"),
+            count("LOSE"),
+            count("DRAW")
+        ));
+        printer.info(format!(
+            "Volforce: {} (you) vs {} ({})",
+            output.volforce, output.rival_volforce, output.rival
+        ));
+        Ok(())
+    }
+}
+
+/// `CmdUsers` is used to list the users in the data source.
+pub struct CmdUsers<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdUsers<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdUsers { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdUsers<T> {
+    fn name(&self) -> &str {
+        "users"
+    }
+    fn usage(&self) -> &str {
+        "users"
+    }
+    fn description(&self) -> &str {
+        "list all the users, the current one is marked with *."
+    }
+
+    fn do_cmd(&self, _: &[String], printer: &Printer) -> Result<()> {
+        let users = self.store.get_users()?;
+        let current = self.store.get_current_user().map(|u| u.refid);
+        let mut tab = table!(["", "refid", "name", "records"]);
+        for u in users.iter() {
+            tab.add_row(row![
+                if current.as_ref() == Some(&u.refid) {
+                    "*"
+                } else {
+                    ""
+                },
+                u.refid,
+                u.name,
+                u.records
+            ]);
+        }
+        printer.print(&tab, &users)?;
+        printer.info(format!("{} user(s) found.", users.len()));
+        Ok(())
+    }
+}
+
+/// `CmdUser` is used to switch the current user.
+pub struct CmdUser<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdUser<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdUser { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdUser<T> {
+    fn name(&self) -> &str {
+        "user"
+    }
+    fn usage(&self) -> &str {
+        "user <refid | name>"
+    }
+    fn description(&self) -> &str {
+        "switch the current user."
+    }
+
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()> {
+        if args.is_empty() {
+            return Err(Error::DoCmdError(String::from("args unmatched.")));
+        }
+        let user = self.store.switch_user(&args.join(" "))?;
+        printer.info(format!(
+            "switched to {} ({}), {} record(s).",
+            user.display_name(),
+            user.refid,
+            self.store.get_all_records().len()
+        ));
+        if let Ok(profile) = self.store.get_profile() {
+            if !profile.name.is_empty() {
+                printer.info(format!("Welcome, {}!", profile.name));
+            }
+        }
+        Ok(())
+    }
+
+    fn complete(&self, args: &str) -> (usize, Vec<String>) {
+        let users = self.store.get_users().unwrap_or_default();
+        completion::complete_token(
+            args,
+            users
+                .into_iter()
+                .flat_map(|u| [u.refid, u.name])
+                .filter(|s| !s.is_empty() && !s.contains(char::is_whitespace)),
+        )
+    }
+}
+
+/// `CmdProfile` is used to get the profile of current user.
+pub struct CmdProfile<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdProfile<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdProfile { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdProfile<T> {
+    fn name(&self) -> &str {
+        "profile"
+    }
+    fn usage(&self) -> &str {
+        "profile"
+    }
+    fn description(&self) -> &str {
+        "get your profile, such as the name, skill level and play count."
+    }
+
+    fn do_cmd(&self, _: &[String], printer: &Printer) -> Result<()> {
+        let profile = self.store.get_profile()?;
+        let tab = table!(
+            [
+                "refid",
+                "name",
+                "skill level",
+                "play count",
+                "packets",
+                "blocks"
+            ],
+            [
+                profile.refid,
+                profile.name,
+                profile.skill_level,
+                profile.play_count,
+                profile.packets,
+                profile.blocks
+            ]
+        );
+        printer.print(&tab, &profile)
+    }
+}
+
+/// `CmdSkill` is used to get the Skill Analyzer course records.
+pub struct CmdSkill<T: DataSource> {
+    store: Rc<T>,
+}
+
+impl<T: DataSource> CmdSkill<T> {
+    pub fn new(store: Rc<T>) -> Self {
+        CmdSkill { store }
+    }
+}
+
+impl<T: DataSource> Cmd for CmdSkill<T> {
+    fn name(&self) -> &str {
+        "skill"
+    }
+    fn usage(&self) -> &str {
+        "skill"
+    }
+    fn description(&self) -> &str {
+        "get your Skill Analyzer course records."
+    }
+
+    fn do_cmd(&self, _: &[String], printer: &Printer) -> Result<()> {
+        let courses = self.store.get_course_records()?;
+        if courses.is_empty() {
+            return Err(Error::DoCmdError(String::from("No course record found.")));
+        }
+        let mut tab = table!([
+            "season",
+            "skill level",
+            "score",
+            "ex score",
+            "grade",
+            "clear type",
+            "rate",
+            "play count",
+            "last updated"
+        ]);
+        for c in courses.iter() {
+            tab.add_row(row![
+                c.season_id,
+                c.skill_level,
+                c.score,
+                c.exscore,
+                c.grade,
+                c.clear_type,
+                format!("{}.{:02}%", c.rate / 100, c.rate % 100),
+                c.play_count,
+                format_date(c.updated_at),
+            ]);
+        }
+        printer.print(&tab, &courses)?;
+        printer.info(format!("{} course record(s) founded.", courses.len()));
+        match courses
+            .iter()
+            .filter(|c| c.is_cleared())
+            .max_by_key(|c| (c.skill_level, c.season_id))
+        {
+            Some(c) => printer.info(format!(
+                "Highest skill level cleared: {} (season {})",
+                c.skill_level, c.season_id
+            )),
+            None => printer.info("No course cleared."),
+        }
         Ok(())
     }
 }
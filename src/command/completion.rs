@@ -0,0 +1,88 @@
+use crate::data_source::DataSource;
+use crate::model::music::Difficulty;
+use crate::storage::fold_text;
+
+use super::filter::ChartFilter;
+
+/// split the last token from `args`, return the start position and the token.
+fn last_token(args: &str) -> (usize, &str) {
+    let start = args
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    (start, &args[start..])
+}
+
+/// complete the last token of `args` by the candidates which start with it (ignoring case).
+pub fn complete_token(
+    args: &str,
+    candidates: impl IntoIterator<Item = String>,
+) -> (usize, Vec<String>) {
+    let (start, token) = last_token(args);
+    let token = token.to_lowercase();
+    let candidates = candidates
+        .into_iter()
+        .filter(|c| c.to_lowercase().starts_with(token.as_str()))
+        .collect();
+    (start, candidates)
+}
+
+/// all the levels of the charts in the music database.
+pub fn levels<T: DataSource>(store: &T) -> Vec<u8> {
+    let mut levels = store
+        .get_all_music()
+        .iter()
+        .flat_map(|m| m.get_charts())
+        .map(|(_, level)| level)
+        .collect::<Vec<u8>>();
+    levels.sort_unstable();
+    levels.dedup();
+    levels
+}
+
+/// complete the music title or music id which is all the text of `args`.
+pub fn complete_music<T: DataSource>(store: &T, args: &str) -> (usize, Vec<String>) {
+    let prefix = args.trim_start();
+    let start = args.len() - prefix.len();
+    let music = store.get_all_music();
+    let mut candidates = if !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_digit()) {
+        music
+            .iter()
+            .map(|m| m.get_id().to_string())
+            .filter(|id| id.starts_with(prefix))
+            .collect::<Vec<String>>()
+    } else {
+        let prefix = fold_text(prefix);
+        music
+            .iter()
+            .map(|m| m.get_name())
+            .filter(|name| fold_text(name).starts_with(prefix.as_str()))
+            .collect::<Vec<String>>()
+    };
+    candidates.sort();
+    candidates.dedup();
+    (start, candidates)
+}
+
+/// complete the `key=value` filter which is the last token of `args`.
+pub fn complete_filter<T: DataSource>(store: &T, args: &str) -> (usize, Vec<String>) {
+    let (_, token) = last_token(args);
+    let candidates = if token.starts_with("difficulty=") {
+        Difficulty::ALL
+            .iter()
+            .map(|d| format!("difficulty={} ", d))
+            .collect::<Vec<String>>()
+    } else if token.starts_with("level=") {
+        levels(store)
+            .iter()
+            .map(|l| format!("level={} ", l))
+            .collect::<Vec<String>>()
+    } else {
+        ChartFilter::KEYS
+            .iter()
+            .map(|k| format!("{}=", k))
+            .collect()
+    };
+    complete_token(args, candidates)
+}
@@ -0,0 +1,79 @@
+use crate::model::music::{Difficulty, Music};
+use crate::{Error, Result};
+
+/// `ChartFilter` filters charts by the metadata of their music,
+/// it is parsed from the `key=value` args of a command.
+///
+/// supported keys: `version`, `genre`, `artist`, `difficulty` and `level`.
+#[derive(Debug, Default)]
+pub struct ChartFilter {
+    version: Option<u8>,
+    genre: Option<u32>,
+    artist: Option<String>,
+    difficulty: Option<Difficulty>,
+    level: Option<u8>,
+}
+
+impl ChartFilter {
+    /// the keys of the supported filters.
+    pub const KEYS: [&'static str; 5] = ["version", "genre", "artist", "difficulty", "level"];
+
+    /// split the `key=value` args from `args`,
+    /// return the filter and the args remain.
+    pub fn parse(args: &[String]) -> Result<(Self, Vec<String>)> {
+        let mut filter = ChartFilter::default();
+        let mut remain = Vec::new();
+        for arg in args {
+            let (key, value) = match arg.split_once('=') {
+                Some(kv) => kv,
+                None => {
+                    remain.push(arg.clone());
+                    continue;
+                }
+            };
+            let invalid = || Error::DoCmdError(format!("invalid filter: {}", arg));
+            match key {
+                "version" => filter.version = Some(value.parse().map_err(|_| invalid())?),
+                "genre" => filter.genre = Some(value.parse().map_err(|_| invalid())?),
+                "level" => filter.level = Some(value.parse().map_err(|_| invalid())?),
+                "artist" => filter.artist = Some(value.to_lowercase()),
+                "difficulty" => filter.difficulty = Some(value.parse().map_err(|_| invalid())?),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok((filter, remain))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.version.is_none()
+            && self.genre.is_none()
+            && self.artist.is_none()
+            && self.difficulty.is_none()
+            && self.level.is_none()
+    }
+
+    /// check the music by `version`, `genre` and `artist`.
+    /// A music which is not found only matches when they are not set.
+    pub fn match_music(&self, music: Option<&Music>) -> bool {
+        let m = match music {
+            Some(m) => m,
+            None => return self.version.is_none() && self.genre.is_none() && self.artist.is_none(),
+        };
+        self.version.is_none_or(|v| m.get_version() == v)
+            && self.genre.is_none_or(|g| m.get_genre() & g != 0)
+            && self
+                .artist
+                .as_ref()
+                .is_none_or(|a| m.get_artist().to_lowercase().contains(a.as_str()))
+    }
+
+    /// check the chart of the music by all the filters,
+    /// `difficulty=inf` matches the charts of all the infinite slots.
+    pub fn match_chart(&self, music: Option<&Music>, difficulty: Difficulty, level: u8) -> bool {
+        self.match_music(music)
+            && self.level.is_none_or(|l| level == l)
+            && self.difficulty.is_none_or(|d| {
+                d == difficulty || (d == Difficulty::Infinite && difficulty.is_infinite_slot())
+            })
+    }
+}
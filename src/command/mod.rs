@@ -1,6 +1,10 @@
 use crate::Result;
 
+#[allow(clippy::module_inception)]
 mod command;
+mod completion;
+mod filter;
+mod printer;
 
 /// the Cmd trait is used to add into Cmdline.
 ///
@@ -12,8 +16,15 @@ pub trait Cmd {
     fn usage(&self) -> &str;
     /// descripton of the command
     fn description(&self) -> &str;
-    /// do the command
-    fn do_cmd(&self, args: &[String]) -> Result<()>;
+    /// do the command, the results are printed by `printer`.
+    fn do_cmd(&self, args: &[String], printer: &Printer) -> Result<()>;
+    /// complete the args of the command, `args` is the text after the command name.
+    ///
+    /// returns the start position of the replaced text in `args` and the candidates.
+    fn complete(&self, _args: &str) -> (usize, Vec<String>) {
+        (0, Vec::new())
+    }
 }
 
 pub use self::command::*;
+pub use self::printer::{OutputFormat, Printer};
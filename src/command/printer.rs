@@ -0,0 +1,64 @@
+use crate::Result;
+use clap::ArgEnum;
+use prettytable::Table;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, io};
+
+/// The format of command outputs.
+#[derive(ArgEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// pretty table for humans.
+    #[default]
+    Table,
+    Json,
+    Csv,
+    Tsv,
+}
+
+/// `Printer` prints the results of commands in the configured `OutputFormat`.
+pub struct Printer {
+    format: OutputFormat,
+}
+
+impl Printer {
+    pub fn new(format: OutputFormat) -> Self {
+        Printer { format }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// print `tab` in table, csv or tsv format, or print `data` in json format.
+    ///
+    /// `tab` and `data` should hold the same content.
+    pub fn print<T: Serialize + ?Sized>(&self, tab: &Table, data: &T) -> Result<()> {
+        match self.format {
+            OutputFormat::Table => {
+                tab.printstd();
+            }
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(io::stdout().lock(), data)?;
+                println!();
+            }
+            OutputFormat::Csv => {
+                tab.to_csv(io::stdout().lock())?;
+            }
+            OutputFormat::Tsv => {
+                let writer = csv::WriterBuilder::new()
+                    .delimiter(b'\t')
+                    .from_writer(io::stdout().lock());
+                tab.to_csv_writer(writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// print messages for humans, they are omitted in machine-readable formats.
+    pub fn info(&self, msg: impl Display) {
+        if self.format == OutputFormat::Table {
+            println!("{}", msg);
+        }
+    }
+}
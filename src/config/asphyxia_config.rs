@@ -23,6 +23,19 @@ pub struct AsphyxiaConfig {
         help = "the path of your game music_db.xml file"
     )]
     pub music_path: String,
+
+    #[clap(
+        name = "asphyxia-game-version",
+        long = "asphyxia-game-version",
+        default_value = "6",
+        help = "the game version of asphyxia, which decides the volforce rule"
+    )]
+    #[serde(default = "default_game_version")]
+    pub game_version: u8,
+
+    #[clap(long, help = "fail on the first malformed line of the record db file")]
+    #[serde(default)]
+    pub strict: bool,
 }
 
 impl Default for AsphyxiaConfig {
@@ -31,6 +44,12 @@ impl Default for AsphyxiaConfig {
             refid: "".to_string(),
             record_path: "savedata.db".to_string(),
             music_path: "music_db.xml".to_string(),
+            game_version: default_game_version(),
+            strict: false,
         }
     }
 }
+
+fn default_game_version() -> u8 {
+    6
+}
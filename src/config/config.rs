@@ -1,9 +1,21 @@
+use crate::command::OutputFormat;
 use crate::config::AsphyxiaConfig;
 use crate::Result;
-use clap::Parser;
+use clap::{ArgEnum, Parser};
 use serde::Deserialize;
 
-use super::BemaniutilsConfig;
+use super::{BemaniutilsConfig, SearchConfig};
+
+/// Where to read the gaming data from.
+#[derive(ArgEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceType {
+    Asphyxia,
+    Bemaniutils,
+    /// try asphyxia first, and then bemaniutils.
+    #[default]
+    Auto,
+}
 
 #[derive(Parser, Debug, Deserialize)]
 #[clap(author, version, about)]
@@ -12,6 +24,44 @@ pub struct Config {
     #[clap(long, short = 'c', default_value = "config.toml")]
     pub config_file: String,
 
+    /// execute one command and exit instead of running the interactive cmdline.
+    #[clap(
+        long,
+        short = 'e',
+        conflicts_with = "script",
+        help = "execute one command and exit"
+    )]
+    #[serde(skip)]
+    pub execute: Option<String>,
+
+    /// execute the commands in a script file and exit, `-` means stdin.
+    #[clap(
+        long,
+        short = 'f',
+        conflicts_with = "execute",
+        help = "execute the commands in a script file (\"-\" for stdin) and exit"
+    )]
+    #[serde(skip)]
+    pub script: Option<String>,
+
+    /// the output format of all the commands, `table` if not set.
+    #[clap(
+        long,
+        short = 'o',
+        arg_enum,
+        help = "the output format of commands [default: table]"
+    )]
+    pub output: Option<OutputFormat>,
+
+    /// the data source to read from, `auto` if not set.
+    #[clap(
+        long,
+        short = 's',
+        arg_enum,
+        help = "the data source to read from [default: auto]"
+    )]
+    pub source: Option<SourceType>,
+
     // reading data from asyphyxia's savedata.db.
     #[clap(flatten)]
     pub asyphyxia: AsphyxiaConfig,
@@ -19,14 +69,23 @@ pub struct Config {
     // reading data from online bemaniutils server.
     #[clap(flatten)]
     pub bemaniutils: BemaniutilsConfig,
+
+    // searching music by names.
+    #[clap(flatten)]
+    pub search: SearchConfig,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             config_file: "config.toml".to_string(),
+            execute: None,
+            script: None,
+            output: None,
+            source: None,
             asyphyxia: AsphyxiaConfig::default(),
             bemaniutils: BemaniutilsConfig::default(),
+            search: SearchConfig::default(),
         }
     }
 }
@@ -44,3 +103,15 @@ impl Config {
         Ok(cfg)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_conflicts_with_script() {
+        assert!(Config::try_parse_from(["ruborute", "-e", "vf"]).is_ok());
+        assert!(Config::try_parse_from(["ruborute", "-f", "-"]).is_ok());
+        assert!(Config::try_parse_from(["ruborute", "-e", "vf", "-f", "-"]).is_err());
+    }
+}
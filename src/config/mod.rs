@@ -1,7 +1,10 @@
 mod asphyxia_config;
 mod bemaniutils_config;
+#[allow(clippy::module_inception)]
 mod config;
+mod search_config;
 
 pub use asphyxia_config::AsphyxiaConfig;
 pub use bemaniutils_config::BemaniutilsConfig;
-pub use config::Config;
+pub use config::{Config, SourceType};
+pub use search_config::SearchConfig;
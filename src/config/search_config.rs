@@ -0,0 +1,35 @@
+use clap::Args;
+use serde::Deserialize;
+
+#[derive(Args, Debug, Deserialize)]
+pub struct SearchConfig {
+    #[clap(
+        long,
+        default_value = "",
+        help = "the path of the music alias file (toml, alias = music id)"
+    )]
+    #[serde(default)]
+    pub alias_path: String,
+
+    #[clap(
+        name = "search-limit",
+        long = "search-limit",
+        default_value = "10",
+        help = "the max number of music matched by a name, 0 means no limit"
+    )]
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            alias_path: "".to_string(),
+            limit: default_limit(),
+        }
+    }
+}
+
+fn default_limit() -> usize {
+    10
+}
@@ -1,50 +1,228 @@
-use crate::config::AsphyxiaConfig;
+use crate::config::{AsphyxiaConfig, SearchConfig};
 use crate::data_source::DataSource;
-use crate::model::record::*;
-use crate::storage::{AsphyxiaRecord, MusicStore};
-use crate::Result;
-use std::{collections::HashMap, fs::File, io::BufReader, path::PathBuf};
+use crate::model::{
+    course::CourseRecord,
+    music::Music,
+    profile::{Profile, User},
+    record::*,
+};
+use crate::storage::{AsphyxiaCourse, AsphyxiaRecord, LoadReport, MusicStore, NeDB, SearchIndex};
+use crate::{Error, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    rc::Rc,
+};
 
 pub struct AsphyxiaDataSource {
-    record_store: RecordStore,
+    /// the record stores of the users loaded, keyed by refid.
+    record_stores: RefCell<HashMap<String, Rc<RecordStore>>>,
+    /// the refid of current user.
+    refid: RefCell<String>,
+    /// all the users found in the db file.
+    users: Vec<User>,
+    /// the db file is kept to load the records of other users.
+    db: NeDB,
+    strict: bool,
     music_store: MusicStore,
+    search_index: SearchIndex,
+    volforce_rule: VolforceRule,
 }
 
 impl AsphyxiaDataSource {
-    pub fn open(conf: AsphyxiaConfig) -> Result<Self> {
+    pub fn open(conf: AsphyxiaConfig, search_conf: &SearchConfig) -> Result<Self> {
         let music_store = MusicStore::open(conf.music_path)?;
-        let record_store = RecordStore::open(conf.refid, conf.record_path, &music_store)?;
+        let search_index = SearchIndex::open(&music_store, search_conf)?;
+        let volforce_rule = VolforceRule::from(conf.game_version);
+        let db = NeDB::open(conf.record_path, conf.strict)?;
+        let (record_store, report) =
+            RecordStore::open(&conf.refid, &db, &music_store, volforce_rule, conf.strict)?;
+        eprintln!("{}", report);
+        eprintln!("{} records loaded.", record_store.records.len());
+        let users = find_users(&db);
+        if users.len() > 1 {
+            eprintln!("{} users found, use `users` to list them.", users.len());
+        }
 
-        println!("data loaded from Asphyxia succeeded!");
+        eprintln!("data loaded from Asphyxia succeeded!");
+        let mut record_stores = HashMap::new();
+        record_stores.insert(conf.refid.clone(), Rc::new(record_store));
         Ok(AsphyxiaDataSource {
+            record_stores: RefCell::new(record_stores),
+            refid: RefCell::new(conf.refid),
+            users,
+            db,
+            strict: conf.strict,
             music_store,
-            record_store,
+            search_index,
+            volforce_rule,
+        })
+    }
+
+    /// find the user by the refid, or the name ignoring case.
+    fn find_user(&self, user: &str) -> Option<&User> {
+        self.users.iter().find(|u| u.refid == user).or_else(|| {
+            self.users
+                .iter()
+                .find(|u| !u.name.is_empty() && u.name.to_lowercase() == user.to_lowercase())
         })
     }
+
+    /// get the record store of the user, which is loaded and cached for the first time.
+    fn get_record_store(&self, refid: &str) -> Result<Rc<RecordStore>> {
+        if let Some(store) = self.record_stores.borrow().get(refid) {
+            return Ok(Rc::clone(store));
+        }
+        let (store, report) = RecordStore::open(
+            refid,
+            &self.db,
+            &self.music_store,
+            self.volforce_rule,
+            self.strict,
+        )?;
+        eprintln!("{}", report);
+        eprintln!("{} records of {} loaded.", store.records.len(), refid);
+        let store = Rc::new(store);
+        self.record_stores
+            .borrow_mut()
+            .insert(refid.to_string(), Rc::clone(&store));
+        Ok(store)
+    }
+
+    /// the record store of current user.
+    fn record_store(&self) -> Rc<RecordStore> {
+        let refid = self.refid.borrow();
+        // the store of current user is always loaded before it is selected.
+        Rc::clone(&self.record_stores.borrow()[refid.as_str()])
+    }
 }
+
+/// find all the users in the db, with the names in their profiles.
+fn find_users(db: &NeDB) -> Vec<User> {
+    let mut users: Vec<User> = Vec::new();
+    for (_, doc) in db.documents() {
+        let refid = match doc.get("__refid").and_then(Value::as_str) {
+            Some(refid) => refid,
+            None => continue,
+        };
+        let idx = match users.iter().position(|u| u.refid == refid) {
+            Some(idx) => idx,
+            None => {
+                users.push(User {
+                    refid: refid.to_string(),
+                    ..Default::default()
+                });
+                users.len() - 1
+            }
+        };
+        match doc.get("collection").and_then(Value::as_str) {
+            Some("music") => users[idx].records += 1,
+            Some("profile") => {
+                if let Some(name) = doc.get("name").and_then(Value::as_str) {
+                    users[idx].name = name.to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+    users
+}
+
 impl DataSource for AsphyxiaDataSource {
     fn get_record_by_id(&self, music_id: Vec<u16>) -> Vec<FullRecord> {
-        for &id in music_id.iter() {
-            println!("Music {}: <{}>", id, self.music_store.get_music_name(id));
-        }
-        self.record_store.get_record_by_id(music_id)
+        self.record_store().get_record_by_id(music_id)
     }
 
     fn get_record_by_name(&self, name: String) -> Vec<FullRecord> {
-        // get id by fuzzy searching
-        let ids = self.music_store.get_id_by_name(&name, true);
-        self.record_store.get_record_by_id(ids)
+        // get ids ranked by searching
+        let ids = self.search_index.search(&name);
+        self.record_store().get_record_by_id(ids)
+    }
+
+    fn get_range_records(&self, from: usize, to: usize) -> Vec<FullRecord> {
+        self.record_store().get_range(from, to)
+    }
+
+    fn get_rival_records(&self, name: &str) -> Result<Vec<FullRecord>> {
+        let refid = match self.find_user(name) {
+            Some(u) => u.refid.as_str(),
+            None => return Err(Error::OtherError(format!("user {} not found.", name))),
+        };
+        let store = self.get_record_store(refid)?;
+        if store.records.is_empty() {
+            return Err(Error::OtherError(format!("no records of {} found.", name)));
+        }
+        Ok(store.get_range(1, usize::MAX))
+    }
+
+    fn get_users(&self) -> Result<Vec<User>> {
+        Ok(self.users.clone())
     }
 
-    fn get_best50_records(&self) -> Vec<FullRecord> {
-        self.record_store.get_best50()
+    fn get_current_user(&self) -> Option<User> {
+        self.find_user(self.refid.borrow().as_str()).cloned()
     }
 
-    fn get_volforce(&self) -> Volfoce {
-        self.record_store.compute_volforce()
+    fn switch_user(&self, user: &str) -> Result<User> {
+        let user = match self.find_user(user) {
+            Some(u) => u.clone(),
+            None => return Err(Error::OtherError(format!("user {} not found.", user))),
+        };
+        self.get_record_store(&user.refid)?;
+        *self.refid.borrow_mut() = user.refid.clone();
+        Ok(user)
+    }
+
+    fn get_course_records(&self) -> Result<Vec<CourseRecord>> {
+        let refid = self.refid.borrow();
+        let mut courses = Vec::new();
+        for (_, doc) in self.db.documents() {
+            if doc.get("collection").and_then(Value::as_str) != Some("course")
+                || doc.get("__refid").and_then(Value::as_str) != Some(refid.as_str())
+            {
+                continue;
+            }
+            courses.push(AsphyxiaCourse::deserialize(doc)?.to_course_record());
+        }
+        courses.sort_by_key(|c| (c.season_id, c.course_id));
+        Ok(courses)
+    }
+
+    fn get_profile(&self) -> Result<Profile> {
+        let refid = self.refid.borrow();
+        let doc = self.db.documents().map(|(_, doc)| doc).find(|doc| {
+            doc.get("collection").and_then(Value::as_str) == Some("profile")
+                && doc.get("__refid").and_then(Value::as_str) == Some(refid.as_str())
+        });
+        match doc {
+            Some(doc) => Ok(Profile::deserialize(doc)?),
+            None => Err(Error::OtherError(format!(
+                "profile of {} not found.",
+                refid
+            ))),
+        }
+    }
+
+    fn get_volforce_rule(&self) -> VolforceRule {
+        self.volforce_rule
     }
     fn get_level_stat(&self, level: Option<u8>) -> Vec<LevelStat> {
-        self.record_store.get_level_stat(level)
+        self.record_store().get_level_stat(level)
+    }
+
+    fn get_music_by_id(&self, music_id: Vec<u16>) -> Vec<Music> {
+        self.music_store.get_music_by_id(&music_id)
+    }
+
+    fn get_music_by_name(&self, name: String) -> Vec<Music> {
+        let ids = self.search_index.search(&name);
+        self.music_store.get_music_by_id(&ids)
+    }
+
+    fn get_all_music(&self) -> Vec<Music> {
+        self.music_store.get_all_music()
     }
 
     fn get_level_count(&self, level: u8) -> usize {
@@ -55,124 +233,113 @@ impl DataSource for AsphyxiaDataSource {
 /// MusicRecordStore is used to get sdvx music record from asphyxia db file.
 struct RecordStore {
     /// music records of current user.
-    /// Vec<Record> contained music records of different levels.
+    /// the inner map contains the records of different charts, keyed by music type.
     records: HashMap<u16, HashMap<u8, FullRecord>>,
 }
 
 impl RecordStore {
-    /// open db file and load all music data to memory
-    pub fn open(user: String, path: impl Into<PathBuf>, music_store: &MusicStore) -> Result<Self> {
-        let path = path.into();
+    /// load all music records of the `user` from the db to memory,
+    /// with a report of the loaded, skipped and rejected lines.
+    ///
+    /// In `strict` mode, an error is returned on the first rejected line.
+    pub fn open(
+        user: &str,
+        db: &NeDB,
+        music_store: &MusicStore,
+        volforce_rule: VolforceRule,
+        strict: bool,
+    ) -> Result<(Self, LoadReport)> {
         let mut records: HashMap<u16, HashMap<u8, FullRecord>> = HashMap::new();
-        // load data
-        let mut reader = BufReader::new(File::open(&path)?);
-        let mut stream =
-            serde_json::Deserializer::from_reader(&mut reader).into_iter::<AsphyxiaRecord>();
-        while let Some(item) = stream.next() {
-            match item {
-                Ok(music_record) => {
-                    if music_record.get_collectoin_str() == "music"
-                        && music_record.get_refid_str() == user
-                    {
-                        // let music = music_store.
-                        let music = music_store.get_music_ref(music_record.get_music_id());
-                        let full_record = music_record.to_full_record(music);
-                        if let Some(rec) = records.get_mut(&full_record.get_music_id()) {
-                            let level = full_record.get_level();
-                            if !rec.contains_key(&level) {
-                                rec.insert(level, full_record);
-                            } else if let Some(r) = rec.get_mut(&level) {
-                                // record the best record
-                                if r.get_volforce() < full_record.get_volforce() {
-                                    *r = full_record;
-                                }
-                            }
-                        } else {
-                            let mut m = HashMap::new();
-                            let id = full_record.get_music_id();
-                            m.insert(full_record.get_level(), full_record);
-                            records.insert(id, m);
+        // load data, the records which are replaced or deleted later are not counted.
+        let mut report = LoadReport::new(db);
+        for (line, doc) in db.documents() {
+            let collection = doc
+                .get("collection")
+                .and_then(Value::as_str)
+                .unwrap_or("(none)");
+            if collection != "music" || doc.get("__refid").and_then(Value::as_str) != Some(user) {
+                report.skip(collection);
+                continue;
+            }
+            let music_record = match AsphyxiaRecord::deserialize(doc) {
+                Ok(r) => r,
+                Err(e) if strict => return Err(Error::MalformedLineError(line, e.to_string())),
+                Err(e) => {
+                    report.reject(line, e);
+                    report.skip(collection);
+                    continue;
+                }
+            };
+            report.load(collection);
+            let music = music_store.get_music_ref(music_record.get_music_id());
+            let full_record = music_record.to_full_record(music, volforce_rule);
+            if let Some(rec) = records.get_mut(&full_record.get_music_id()) {
+                let music_type = music_record.get_music_type();
+                match rec.entry(music_type) {
+                    Entry::Vacant(e) => {
+                        e.insert(full_record);
+                    }
+                    Entry::Occupied(mut e) => {
+                        // record the best record
+                        if e.get().get_volforce() < full_record.get_volforce() {
+                            e.insert(full_record);
                         }
                     }
                 }
-                _ => {}
+            } else {
+                let mut m = HashMap::new();
+                let id = full_record.get_music_id();
+                m.insert(music_record.get_music_type(), full_record);
+                records.insert(id, m);
             }
         }
-        println!("{} records loaded.", records.len());
-        Ok(RecordStore { records })
+        Ok((RecordStore { records }, report))
     }
 
-    /// get music record by music id
+    /// get music record by music id, in the order of `music_id`.
     pub fn get_record_by_id(&self, music_id: Vec<u16>) -> Vec<FullRecord> {
-        self.records
-            .iter()
-            .filter(|(id, _)| music_id.contains(&id))
-            .map(|(_, rec)| rec)
-            .collect::<Vec<&HashMap<u8, FullRecord>>>()
-            .into_iter()
-            .map(|map| {
-                map.values()
-                    .into_iter()
-                    .cloned()
-                    .collect::<Vec<FullRecord>>()
-            })
-            .flatten()
-            .collect::<Vec<FullRecord>>()
+        let mut records = Vec::new();
+        for id in music_id.iter() {
+            if let Some(map) = self.records.get(id) {
+                let mut recs = map.iter().collect::<Vec<(&u8, &FullRecord)>>();
+                recs.sort_by_key(|(music_type, _)| **music_type);
+                records.extend(recs.into_iter().map(|(_, rec)| rec.clone()));
+            }
+        }
+        records
     }
 
-    /// get the top 50 vf records
-    pub fn get_best50(&self) -> Vec<FullRecord> {
+    /// get the records ranked from `from` to `to` in vf order
+    pub fn get_range(&self, from: usize, to: usize) -> Vec<FullRecord> {
         let mut records = self
             .records
-            .iter()
-            .map(|(_, rec)| rec)
-            .collect::<Vec<&HashMap<u8, FullRecord>>>()
-            .into_iter()
-            .map(|map| {
-                map.values()
-                    .into_iter()
-                    .cloned()
-                    .collect::<Vec<FullRecord>>()
-            })
-            .flatten()
+            .values()
+            .flat_map(|map| map.values().cloned())
             .collect::<Vec<FullRecord>>();
         records.sort_by_key(|rec| rec.get_volforce());
         records
             .iter()
             .rev()
-            .take(50)
+            .skip(from.saturating_sub(1))
+            .take(to.saturating_add(1).saturating_sub(from.max(1)))
             .cloned()
             .collect::<Vec<FullRecord>>()
     }
 
-    /// compute the complete volforce
-    pub fn compute_volforce(&self) -> Volfoce {
-        let best50 = self.get_best50();
-        let mut vf_sum = 0;
-        for rec in best50.iter() {
-            vf_sum += rec.get_volforce().get_internal();
-        }
-        Volfoce::new(vf_sum / 50)
-    }
-
     /// get clear and grade type of a level.
     /// when level is None, return all level stat.
     pub fn get_level_stat(&self, level: Option<u8>) -> Vec<LevelStat> {
         let mut level_stat: HashMap<u8, LevelStat> = HashMap::new();
         for r in self
             .records
-            .iter()
-            .map(|(_, rec)| rec)
-            .collect::<Vec<&HashMap<u8, FullRecord>>>()
-            .into_iter()
-            .map(|map| map.values().collect::<Vec<&FullRecord>>())
-            .flatten()
+            .values()
+            .flat_map(|map| map.values())
             .filter(|r| match level {
                 Some(l) => r.get_level() == l,
                 None => true,
             })
         {
-            let mut stat = LevelStat::new(r.get_level(), 0, 0, 0, 0, 0, 0, 0, 1);
+            let mut stat = LevelStat::new(r.get_level(), 1);
             match r.get_clear_type() {
                 ClearType::Complete => stat.incr_nc_num(1),
                 ClearType::HardComplete => stat.incr_hc_num(1),
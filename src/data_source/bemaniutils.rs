@@ -1,18 +1,25 @@
 use std::collections::HashMap;
 
 use super::DataSource;
-use crate::config::BemaniutilsConfig;
-use crate::model::{music::*, record::*};
-use crate::storage::MusicStore;
+use crate::config::{BemaniutilsConfig, SearchConfig};
+use crate::model::{course::CourseRecord, music::*, profile::Profile, record::*};
+use crate::storage::{normalize_text, MusicStore, SearchIndex};
 use crate::{errors, Result};
+use chrono::{Local, TimeZone};
 use mysql::prelude::*;
 use mysql::*;
-use rust_fuzzy_search::fuzzy_compare;
 use serde::Deserialize;
 
 pub struct BemaniutilsDataSource {
     records: Vec<FullRecord>,
+    courses: Vec<CourseRecord>,
+    profile: Option<Profile>,
     music_store: MusicStore,
+    search_index: SearchIndex,
+    volforce_rule: VolforceRule,
+    /// to query the records of rivals.
+    pool: Pool,
+    game_version: u8,
 }
 
 impl DataSource for BemaniutilsDataSource {
@@ -24,21 +31,23 @@ impl DataSource for BemaniutilsDataSource {
             .cloned()
             .collect()
     }
-    /// Get records by name. The implementation is probably fuzzy search.
+    /// Get records by name, ranked by the similarity of the names.
     fn get_record_by_name(&self, name: String) -> Vec<FullRecord> {
+        let mut records = Vec::new();
+        for id in self.search_index.search(&name) {
+            records.extend(self.records.iter().filter(|r| r.music_id == id).cloned());
+        }
+        records
+    }
+    /// Get records ranked from `from` to `to` in volforce order.
+    fn get_range_records(&self, from: usize, to: usize) -> Vec<FullRecord> {
         self.records
             .iter()
-            .filter(|r| {
-                name.to_lowercase() == r.music_name.to_lowercase()
-                    || fuzzy_compare(&name.to_lowercase(), &r.music_name.to_lowercase()) > 0.5
-            })
+            .skip(from.saturating_sub(1))
+            .take(to.saturating_add(1).saturating_sub(from.max(1)))
             .cloned()
             .collect()
     }
-    /// Get best 50 records of current user.
-    fn get_best50_records(&self) -> Vec<FullRecord> {
-        self.records.iter().take(50).cloned().collect()
-    }
     /// Show how many CLEARs and GRADEs dose the user have at each type at the level.
     /// If `level` is `None`, return all level stats.
     fn get_level_stat(&self, level: Option<u8>) -> Vec<LevelStat> {
@@ -47,7 +56,7 @@ impl DataSource for BemaniutilsDataSource {
             Some(l) => r.get_level() == l,
             None => true,
         }) {
-            let mut stat = LevelStat::new(r.get_level(), 0, 0, 0, 0, 0, 0, 0, 1);
+            let mut stat = LevelStat::new(r.get_level(), 1);
             match r.get_clear_type() {
                 ClearType::Complete => stat.incr_nc_num(1),
                 ClearType::HardComplete => stat.incr_hc_num(1),
@@ -75,43 +84,93 @@ impl DataSource for BemaniutilsDataSource {
         r
     }
 
+    fn get_music_by_id(&self, music_id: Vec<u16>) -> Vec<Music> {
+        self.music_store.get_music_by_id(&music_id)
+    }
+
+    fn get_music_by_name(&self, name: String) -> Vec<Music> {
+        let ids = self.search_index.search(&name);
+        self.music_store.get_music_by_id(&ids)
+    }
+
+    fn get_all_music(&self) -> Vec<Music> {
+        self.music_store.get_all_music()
+    }
+
+    fn get_rival_records(&self, name: &str) -> Result<Vec<FullRecord>> {
+        let mut conn = self.pool.get_conn()?;
+        let user_id = get_user_id(&mut conn, name)?;
+        let records =
+            get_records_from_db(&mut conn, user_id, self.game_version, self.volforce_rule)?;
+        Ok(records.into_iter().rev().collect())
+    }
+
+    fn get_course_records(&self) -> Result<Vec<CourseRecord>> {
+        Ok(self.courses.clone())
+    }
+
+    fn get_profile(&self) -> Result<Profile> {
+        self.profile
+            .clone()
+            .ok_or_else(|| errors::Error::OtherError("bemanitutils: profile not found".to_string()))
+    }
+
+    fn get_volforce_rule(&self) -> VolforceRule {
+        self.volforce_rule
+    }
+
     fn get_level_count(&self, level: u8) -> usize {
         self.music_store.get_level_count(level)
     }
 }
 
 impl BemaniutilsDataSource {
-    pub fn open(conf: BemaniutilsConfig) -> Result<Self> {
+    pub fn open(conf: BemaniutilsConfig, search_conf: &SearchConfig) -> Result<Self> {
         // read all need data when open
         let url = format!(
             "mysql://{}:{}@{}:{}/{}",
             conf.db_user, conf.db_password, conf.db_address, conf.db_port, conf.db_name
         );
-        let pool = Pool::new(mysql::Opts::from_url(url.as_str()).unwrap())?;
+        let opts = mysql::Opts::from_url(url.as_str()).map_err(|e| {
+            errors::Error::OtherError(format!("bemanitutils: invalid database url: {}", e))
+        })?;
+        let pool = Pool::new(opts)?;
         let mut conn = pool.get_conn()?;
         // get user id by username first
-        let user_id: u16 = if let Some(id) =
-            conn.exec_first("SELECT id FROM user WHERE username = ?", (conf.username,))?
-        {
-            id
-        } else {
-            return Err(errors::Error::OtherError(
-                "bemanitutils: username not found".to_string(),
-            ));
-        };
-
-        let full_records = get_records_from_db(&mut conn, user_id, conf.game_version)?;
-        let music = get_music_from_db(&mut conn)?;
-
-        println!("{} records loaded.", full_records.len());
-        println!("data loaded from Bemaniutils server database succeeded!");
+        let user_id = get_user_id(&mut conn, &conf.username)?;
+
+        let volforce_rule = VolforceRule::from(conf.game_version);
+        let full_records =
+            get_records_from_db(&mut conn, user_id, conf.game_version, volforce_rule)?;
+        let music = get_music_from_db(&mut conn, conf.game_version)?;
+        let profile = get_profile_from_db(&mut conn, user_id, conf.game_version)?;
+        let courses = get_courses_from_db(&mut conn, user_id, conf.game_version)?;
+        let search_index = SearchIndex::open(&music, search_conf)?;
+
+        eprintln!("{} records loaded.", full_records.len());
+        eprintln!("data loaded from Bemaniutils server database succeeded!");
         Ok(Self {
             records: full_records.into_iter().rev().collect(),
+            courses,
+            profile,
             music_store: music,
+            search_index,
+            volforce_rule,
+            pool,
+            game_version: conf.game_version,
         })
     }
 }
 
+fn get_user_id(conn: &mut PooledConn, username: &str) -> Result<u16> {
+    match conn.exec_first("SELECT id FROM user WHERE username = ?", (username,))? {
+        Some(id) => Ok(id),
+        None => Err(errors::Error::OtherError(
+            "bemanitutils: username not found".to_string(),
+        )),
+    }
+}
+
 impl BemaniutilsDataSource {
     pub fn get_records(&self) -> Vec<FullRecord> {
         self.records.clone()
@@ -122,6 +181,7 @@ fn get_records_from_db(
     conn: &mut PooledConn,
     user_id: u16,
     version: u8,
+    volforce_rule: VolforceRule,
 ) -> Result<Vec<FullRecord>> {
     // get records by user id
     #[derive(Debug, Deserialize)]
@@ -132,21 +192,26 @@ fn get_records_from_db(
         points: u32,
         sdata: String,
         mdata: String,
+        timestamp: i64,
+        update: i64,
     }
 
-    let sql = "SELECT music.songid AS songid, music.name AS name, music.chart AS chart, score.points AS points, score.data AS sdata, music.data AS mdata \
+    let sql = "SELECT music.songid AS songid, music.name AS name, music.chart AS chart, score.points AS points, score.data AS sdata, music.data AS mdata, \
+    score.timestamp AS timestamp, score.`update` AS `update` \
     FROM score, music \
     WHERE score.userid = ? AND score.musicid = music.id AND music.game = 'sdvx' AND music.version = ?";
     let result: Vec<DBRecords> = conn.exec_map(
         sql,
         (user_id, version),
-        |(songid, name, chart, points, sdata, mdata)| DBRecords {
+        |(songid, name, chart, points, sdata, mdata, timestamp, update)| DBRecords {
             songid,
             name,
             chart,
             points,
             sdata,
             mdata,
+            timestamp,
+            update,
         },
     )?;
 
@@ -156,11 +221,26 @@ fn get_records_from_db(
             #[derive(Debug, Deserialize)]
             struct Mdata {
                 difficulty: u8,
+                #[serde(default)]
+                inf_ver: u8,
+            }
+            #[derive(Debug, Deserialize, Default)]
+            struct Stats {
+                #[serde(default)]
+                btn_rate: u16,
+                #[serde(default)]
+                long_rate: u16,
+                #[serde(default)]
+                vol_rate: u16,
             }
             #[derive(Debug, Deserialize)]
             struct SData {
                 grade: u16,
                 clear_type: u16,
+                #[serde(default, alias = "ex_score")]
+                exscore: u32,
+                #[serde(default)]
+                stats: Stats,
             }
             let mdata: Mdata = serde_json::from_str(r.mdata.as_str()).unwrap();
             let sdata: SData = serde_json::from_str(r.sdata.as_str()).unwrap();
@@ -169,13 +249,25 @@ fn get_records_from_db(
 
             FullRecord {
                 music_id: r.songid,
-                music_name: r.name,
-                difficulty: Difficulty::from(r.chart),
+                music_name: normalize_text(&r.name),
+                difficulty: Difficulty::from_chart(r.chart, mdata.inf_ver),
                 level: mdata.difficulty,
                 score: r.points,
-                grade: grade,
-                clear_type: clear_type,
-                volfoce: compute_volforce(mdata.difficulty, r.points, grade, clear_type),
+                grade,
+                clear_type,
+                volfoce: volforce_rule.compute_volforce(
+                    mdata.difficulty,
+                    r.points,
+                    grade,
+                    clear_type,
+                ),
+                exscore: sdata.exscore,
+                button_rate: sdata.stats.btn_rate,
+                long_rate: sdata.stats.long_rate,
+                vol_rate: sdata.stats.vol_rate,
+                // the timestamps of bemaniutils are seconds since unix epoch
+                created_at: Local.timestamp_opt(r.timestamp, 0).single(),
+                updated_at: Local.timestamp_opt(r.update, 0).single(),
             }
         })
         .collect::<Vec<FullRecord>>();
@@ -184,47 +276,144 @@ fn get_records_from_db(
     Ok(full_records)
 }
 
-fn get_music_from_db(conn: &mut PooledConn) -> Result<MusicStore> {
+/// get the profile of the user in the game version, by the refid of the user.
+fn get_profile_from_db(
+    conn: &mut PooledConn,
+    user_id: u16,
+    version: u8,
+) -> Result<Option<Profile>> {
+    let sql = "SELECT refid.refid AS refid, profile.data AS data \
+    FROM refid, profile \
+    WHERE refid.userid = ? AND refid.game = 'sdvx' AND refid.version = ? AND profile.refid = refid.refid";
+    let result: Option<(String, String)> = conn.exec_first(sql, (user_id, version))?;
+    match result {
+        Some((refid, data)) => {
+            let mut profile: Profile = serde_json::from_str(data.as_str())?;
+            profile.refid = refid;
+            Ok(Some(profile))
+        }
+        None => Ok(None),
+    }
+}
+
+/// get the Skill Analyzer course records of the user in the game version,
+/// which are the achievements of type `course` with id `season * 100 + course`.
+fn get_courses_from_db(
+    conn: &mut PooledConn,
+    user_id: u16,
+    version: u8,
+) -> Result<Vec<CourseRecord>> {
+    #[derive(Debug, Deserialize)]
+    struct CourseData {
+        #[serde(default)]
+        score: u32,
+        #[serde(default, alias = "ex_score")]
+        exscore: u32,
+        #[serde(default)]
+        clear_type: u16,
+        #[serde(default)]
+        grade: u16,
+        #[serde(default)]
+        rate: u32,
+        #[serde(default)]
+        count: u32,
+    }
+
+    let sql = "SELECT achievement.id AS id, achievement.timestamp AS timestamp, \
+    achievement.data AS data \
+    FROM refid, achievement \
+    WHERE refid.userid = ? AND refid.game = 'sdvx' AND refid.version = ? \
+    AND achievement.refid = refid.refid AND achievement.type = 'course'";
+    let result: Vec<(u32, i64, String)> = conn.exec(sql, (user_id, version))?;
+    let mut courses = Vec::new();
+    for (id, timestamp, data) in result {
+        let data: CourseData = serde_json::from_str(data.as_str())?;
+        let (season_id, course_id) = ((id / 100) as u16, (id % 100) as u16);
+        courses.push(CourseRecord {
+            season_id,
+            course_id,
+            skill_level: CourseRecord::skill_level_of(course_id),
+            score: data.score,
+            exscore: data.exscore,
+            clear_type: ClearType::from(data.clear_type),
+            grade: Grade::from(data.grade),
+            rate: data.rate,
+            play_count: data.count,
+            updated_at: Local.timestamp_opt(timestamp, 0).single(),
+        });
+    }
+    courses.sort_by_key(|c| (c.season_id, c.course_id));
+    Ok(courses)
+}
+
+/// build the music store from the sdvx music of the game version,
+/// every chart of a music is a row of the `music` table.
+fn get_music_from_db(conn: &mut PooledConn, version: u8) -> Result<MusicStore> {
     #[derive(Debug, Deserialize)]
     struct DBMusic {
         songid: u16,
         chart: u8,
         name: String,
+        /// NULL for some music.
+        artist: Option<String>,
         data: String,
     }
     #[derive(Debug, Deserialize)]
     struct Mdata {
         difficulty: u8,
+        #[serde(default)]
+        inf_ver: u8,
+        #[serde(default)]
+        bpm_min: f64,
+        #[serde(default)]
+        bpm_max: f64,
     }
 
-    let sql = "SELECT songid, chart, name, data FROM music";
-    let result: Vec<DBMusic> = conn.exec_map(sql, (), |(songid, chart, name, data)| DBMusic {
-        songid,
-        chart,
-        name,
-        data,
-    })?;
+    let sql =
+        "SELECT songid, chart, name, artist, data FROM music WHERE game = 'sdvx' AND version = ?";
+    let result: Vec<DBMusic> =
+        conn.exec_map(sql, (version,), |(songid, chart, name, artist, data)| {
+            DBMusic {
+                songid,
+                chart,
+                name,
+                artist,
+                data,
+            }
+        })?;
     let mut music: HashMap<u16, Music> = HashMap::new();
     let mut name_id_map: HashMap<String, u16> = HashMap::new();
 
     for m in result.iter() {
-        let mdata: Mdata = serde_json::from_str(m.data.as_str()).unwrap();
+        let mdata: Mdata = serde_json::from_str(m.data.as_str())?;
         if mdata.difficulty == 0 {
             continue;
         }
-        if !name_id_map.contains_key(&m.name) {
-            name_id_map.insert(m.name.clone(), m.songid);
-            music.insert(m.songid, Music::default());
-        }
-        if let Some(mut ms) = music.get_mut(&m.songid) {
-            match m.chart {
-                0 => ms.difficulty.novice.level = mdata.difficulty,
-                1 => ms.difficulty.advanced.level = mdata.difficulty,
-                2 => ms.difficulty.exhaust.level = mdata.difficulty,
-                3 => ms.difficulty.infinite.level = mdata.difficulty,
-                _ => ms.difficulty.maximum.level = mdata.difficulty,
+        let ms = music.entry(m.songid).or_insert_with(|| Music {
+            id: m.songid,
+            info: MusicInfo {
+                name: normalize_text(&m.name),
+                artist_name: normalize_text(m.artist.as_deref().unwrap_or_default()),
+                // the bpm of bemaniutils is the real one, and it is multiplied by 100 in `MusicInfo`
+                bpm_min: (mdata.bpm_min * 100.0).round() as u32,
+                bpm_max: (mdata.bpm_max * 100.0).round() as u32,
+                ..Default::default()
+            },
+            difficulty: MusicDiffculty::default(),
+        });
+        match Difficulty::from_chart(m.chart, mdata.inf_ver) {
+            Difficulty::Novice => ms.difficulty.novice.level = mdata.difficulty,
+            Difficulty::Advanced => ms.difficulty.advanced.level = mdata.difficulty,
+            Difficulty::Exhaust => ms.difficulty.exhaust.level = mdata.difficulty,
+            Difficulty::Maximum | Difficulty::Unknown => {
+                ms.difficulty.maximum.level = mdata.difficulty
+            }
+            d => {
+                ms.difficulty.infinite.level = mdata.difficulty;
+                ms.info.inf_ver = d.get_inf_ver().unwrap_or_default();
             }
         }
+        name_id_map.entry(ms.get_name()).or_insert(m.songid);
     }
 
     Ok(MusicStore { music, name_id_map })
@@ -1,7 +1,12 @@
 mod asphyxia;
 mod bemaniutils;
 
-use crate::model::record::{FullRecord, LevelStat, Volfoce};
+use crate::model::course::CourseRecord;
+use crate::model::music::Music;
+use crate::model::profile::{Profile, User};
+use crate::model::record::{FullRecord, LevelStat, Volfoce, VolforceRule};
+use crate::{Error, Result};
+use chrono::{DateTime, Local};
 
 pub use asphyxia::AsphyxiaDataSource;
 pub use bemaniutils::BemaniutilsDataSource;
@@ -11,18 +16,78 @@ pub trait DataSource {
     fn get_record_by_id(&self, music_id: Vec<u16>) -> Vec<FullRecord>;
     /// Get records by name. The implementation is probably fuzzy search.
     fn get_record_by_name(&self, name: String) -> Vec<FullRecord>;
+    /// Get records of current user ranked from `from` to `to` in volforce order.
+    /// Ranks start from 1 and both ends are inclusive.
+    fn get_range_records(&self, from: usize, to: usize) -> Vec<FullRecord>;
+    /// Get all records of current user in volforce order.
+    fn get_all_records(&self) -> Vec<FullRecord> {
+        self.get_range_records(1, usize::MAX)
+    }
     /// Get best 50 records of current user.
-    fn get_best50_records(&self) -> Vec<FullRecord>;
+    fn get_best50_records(&self) -> Vec<FullRecord> {
+        self.get_range_records(1, 50)
+    }
+    /// Get the records of another player (a rival) by the name, in volforce order.
+    fn get_rival_records(&self, name: &str) -> Result<Vec<FullRecord>>;
+    /// Get all the users in the data source.
+    fn get_users(&self) -> Result<Vec<User>> {
+        Err(Error::OtherError(String::from(
+            "multiple users are not supported by the data source.",
+        )))
+    }
+    /// Get the current user.
+    fn get_current_user(&self) -> Option<User> {
+        None
+    }
+    /// Switch the current user by the refid or the name, and return the user.
+    /// The records of the user are loaded when it is selected for the first time.
+    fn switch_user(&self, _user: &str) -> Result<User> {
+        Err(Error::OtherError(String::from(
+            "multiple users are not supported by the data source.",
+        )))
+    }
+    /// Get the Skill Analyzer course records of current user, sorted by season and course.
+    fn get_course_records(&self) -> Result<Vec<CourseRecord>>;
+    /// Get the profile of current user.
+    fn get_profile(&self) -> Result<Profile>;
+    /// Get the rule to compute volforce of current game version.
+    fn get_volforce_rule(&self) -> VolforceRule;
     /// Get current user's vf
     fn get_volforce(&self) -> Volfoce {
-        let best50 = self.get_best50_records();
-        let vf_sum: u32 = best50.iter().map(|r| r.get_volforce().get_internal()).sum();
-        Volfoce::new(vf_sum / 50)
+        self.get_volforce_rule()
+            .total_volforce(&self.get_best50_records())
+    }
+    /// Get current user's vf at each of `dates`, which is computed by the best 50 of
+    /// the records updated before the date.
+    ///
+    /// Only the current records are kept, so a chart counts since its current record is set.
+    fn get_volforce_history(&self, dates: &[DateTime<Local>]) -> Vec<Volfoce> {
+        let rule = self.get_volforce_rule();
+        let records = self.get_all_records();
+        dates
+            .iter()
+            .map(|date| {
+                let best50 = records
+                    .iter()
+                    .filter(|r| r.get_updated_at().is_some_and(|d| d < *date))
+                    .take(50)
+                    .cloned()
+                    .collect::<Vec<FullRecord>>();
+                rule.total_volforce(&best50)
+            })
+            .collect()
     }
     /// Show how many CLEARs and GRADEs dose the user have at each type at the level.
     /// If `level` is `None`, return all level stats.
     fn get_level_stat(&self, level: Option<u8>) -> Vec<LevelStat>;
 
+    /// Get music information of music_ids
+    fn get_music_by_id(&self, music_id: Vec<u16>) -> Vec<Music>;
+    /// Get music information by name. The implementation is probably fuzzy search.
+    fn get_music_by_name(&self, name: String) -> Vec<Music>;
+    /// Get all music in the music database, in music id order.
+    fn get_all_music(&self) -> Vec<Music>;
+
     /// Get the number of music of one level
     fn get_level_count(&self, level: u8) -> usize;
 }
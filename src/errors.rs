@@ -1,3 +1,6 @@
+// the `Fail` derive of failure expands to impls inside an anonymous const.
+#![allow(non_local_definitions)]
+
 use failure::Fail;
 use std::{io, result, string};
 
@@ -17,6 +20,10 @@ pub enum Error {
     MySQLError(mysql::Error),
     #[fail(display = "serde json error: {}", _0)]
     SerdeJSONError(serde_json::Error),
+    #[fail(display = "csv error: {}", _0)]
+    CsvError(csv::Error),
+    #[fail(display = "malformed line {}: {}", _0, _1)]
+    MalformedLineError(usize, String),
     #[fail(display = "{}", _0)]
     OtherError(String),
 }
@@ -57,4 +64,10 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::CsvError(e)
+    }
+}
+
 pub type Result<T> = result::Result<T, Error>;
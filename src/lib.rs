@@ -7,6 +7,7 @@ mod model;
 pub mod storage;
 
 pub use crate::cmdline::Cmdline;
+pub use crate::command::OutputFormat;
 pub use crate::data_source::*;
 pub use crate::errors::{Error, Result};
-pub use config::Config;
+pub use config::{Config, SourceType};
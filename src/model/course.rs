@@ -0,0 +1,38 @@
+use super::record::{ClearType, Grade};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+/// `CourseRecord` is the best result of a Skill Analyzer course.
+#[derive(Debug, Clone, Serialize)]
+pub struct CourseRecord {
+    pub season_id: u16,
+    pub course_id: u16,
+    /// the skill level of the course, by `CourseRecord::skill_level_of`.
+    pub skill_level: u16,
+    pub score: u32,
+    pub exscore: u32,
+    pub clear_type: ClearType,
+    pub grade: Grade,
+    /// the achievement rate in 0.01%.
+    pub rate: u32,
+    /// the number of times the course is played.
+    pub play_count: u32,
+    pub updated_at: Option<DateTime<Local>>,
+}
+
+impl CourseRecord {
+    /// the skill level of the course `course_id` in its season.
+    ///
+    /// The servers save the course id only. The courses of a season are assumed to be
+    /// numbered from 0 in the order of the skill level (course 0 is level 1), as the
+    /// regular seasons are, so the level of an extra course may be wrong and
+    /// `course_id` is kept in the record as well.
+    pub fn skill_level_of(course_id: u16) -> u16 {
+        course_id.saturating_add(1)
+    }
+
+    /// the course is cleared when the clear type is `Complete` or better.
+    pub fn is_cleared(&self) -> bool {
+        self.clear_type >= ClearType::Complete
+    }
+}
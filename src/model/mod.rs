@@ -1,2 +1,5 @@
+pub mod course;
 pub mod music;
+pub mod profile;
 pub mod record;
+pub mod snapshot;
@@ -1,5 +1,7 @@
-use serde::Deserialize;
-use std::fmt::Display;
+use crate::Error;
+use chrono::NaiveDate;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt::Display, str::FromStr};
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Difficulty {
@@ -11,22 +13,73 @@ pub enum Difficulty {
     Gravity,
     Heaven,
     Vivid,
+    Exceed,
     Maximum,
 }
 
 impl Difficulty {
+    /// all the known difficulties.
+    pub const ALL: [Difficulty; 9] = [
+        Difficulty::Novice,
+        Difficulty::Advanced,
+        Difficulty::Exhaust,
+        Difficulty::Infinite,
+        Difficulty::Gravity,
+        Difficulty::Heaven,
+        Difficulty::Vivid,
+        Difficulty::Exceed,
+        Difficulty::Maximum,
+    ];
+
+    /// resolve the chart of the infinite slot by the `inf_ver` of the music.
     pub fn inf_ver(&self, inf_ver: u8) -> Self {
-        if *self == Difficulty::Infinite {
+        if self.is_infinite_slot() {
             match inf_ver {
+                2 => Difficulty::Infinite,
                 3 => Difficulty::Gravity,
                 4 => Difficulty::Heaven,
                 5 => Difficulty::Vivid,
+                6 => Difficulty::Exceed,
                 _ => *self,
             }
         } else {
             *self
         }
     }
+    /// get the `inf_ver` of the chart, `None` if it is not in the infinite slot.
+    pub fn get_inf_ver(&self) -> Option<u8> {
+        match *self {
+            Difficulty::Infinite => Some(2),
+            Difficulty::Gravity => Some(3),
+            Difficulty::Heaven => Some(4),
+            Difficulty::Vivid => Some(5),
+            Difficulty::Exceed => Some(6),
+            _ => None,
+        }
+    }
+    pub fn is_infinite_slot(&self) -> bool {
+        self.get_inf_ver().is_some()
+    }
+    /// the music type of the chart, all the charts of the infinite slot are 3.
+    /// `None` for `Unknown`, which has no music type.
+    pub fn to_music_type(self) -> Option<u8> {
+        match self {
+            Difficulty::Novice => Some(0),
+            Difficulty::Advanced => Some(1),
+            Difficulty::Exhaust => Some(2),
+            Difficulty::Infinite
+            | Difficulty::Gravity
+            | Difficulty::Heaven
+            | Difficulty::Vivid
+            | Difficulty::Exceed => Some(3),
+            Difficulty::Maximum => Some(4),
+            Difficulty::Unknown => None,
+        }
+    }
+    /// get the difficulty of a chart by its music type and the `inf_ver` of the music.
+    pub fn from_chart(music_type: u8, inf_ver: u8) -> Self {
+        Difficulty::from(music_type).inf_ver(inf_ver)
+    }
 }
 
 impl Display for Difficulty {
@@ -39,12 +92,40 @@ impl Display for Difficulty {
             Difficulty::Gravity => write!(f, "GRV"),
             Difficulty::Heaven => write!(f, "HVN"),
             Difficulty::Vivid => write!(f, "VVD"),
+            Difficulty::Exceed => write!(f, "XCD"),
             Difficulty::Maximum => write!(f, "MXM"),
             Difficulty::Unknown => write!(f, "UNKNOWN"),
         }
     }
 }
 
+/// parse a difficulty from its short name (`NOV`, `MXM`, ...) or full name, ignoring case.
+impl FromStr for Difficulty {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "nov" | "novice" => Ok(Difficulty::Novice),
+            "adv" | "advanced" => Ok(Difficulty::Advanced),
+            "exh" | "exhaust" => Ok(Difficulty::Exhaust),
+            "inf" | "infinite" => Ok(Difficulty::Infinite),
+            "grv" | "gravity" => Ok(Difficulty::Gravity),
+            "hvn" | "heavenly" | "heaven" => Ok(Difficulty::Heaven),
+            "vvd" | "vivid" => Ok(Difficulty::Vivid),
+            "xcd" | "exceed" => Ok(Difficulty::Exceed),
+            "mxm" | "maximum" => Ok(Difficulty::Maximum),
+            "unknown" => Ok(Difficulty::Unknown),
+            _ => Err(Error::OtherError(format!("unknown difficulty: {}", s))),
+        }
+    }
+}
+
+impl Serialize for Difficulty {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl From<u8> for Difficulty {
     /// from music type to Difficulty
     fn from(d: u8) -> Self {
@@ -59,31 +140,20 @@ impl From<u8> for Difficulty {
     }
 }
 
-impl Into<u8> for Difficulty {
-    fn into(self) -> u8 {
-        match self {
-            Difficulty::Novice => 0,
-            Difficulty::Advanced => 1,
-            Difficulty::Exhaust => 2,
-            Difficulty::Infinite => 3,
-            Difficulty::Maximum => 4,
-            _ => 0,
-        }
+impl<'de> Deserialize<'de> for Difficulty {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy, Default)]
 pub struct DiffInfo {
     #[serde(rename = "difnum")]
     pub level: u8,
 }
 
-impl Default for DiffInfo {
-    fn default() -> Self {
-        DiffInfo { level: 0 }
-    }
-}
-
 #[derive(Debug, Deserialize, PartialEq, Clone, Copy, Default)]
 pub struct MusicDiffculty {
     #[serde(default)]
@@ -102,7 +172,30 @@ pub struct MusicDiffculty {
 pub struct MusicInfo {
     #[serde(rename = "title_name")]
     pub name: String,
+    #[serde(default)]
     pub inf_ver: u8,
+    #[serde(default)]
+    pub artist_name: String,
+    /// the title in ascii characters.
+    #[serde(default)]
+    pub ascii: String,
+    #[serde(default)]
+    pub title_yomigana: String,
+    /// bpm multiplied by 100.
+    #[serde(default)]
+    pub bpm_max: u32,
+    /// bpm multiplied by 100.
+    #[serde(default)]
+    pub bpm_min: u32,
+    /// bits of the genres the music belongs to.
+    #[serde(default)]
+    pub genre: u32,
+    /// the game version the music is added in.
+    #[serde(default)]
+    pub version: u8,
+    /// release date in the format of `YYYYMMDD`.
+    #[serde(default)]
+    pub distribution_date: u32,
 }
 
 impl Clone for MusicInfo {
@@ -110,6 +203,14 @@ impl Clone for MusicInfo {
         MusicInfo {
             name: self.name.clone(),
             inf_ver: self.inf_ver,
+            artist_name: self.artist_name.clone(),
+            ascii: self.ascii.clone(),
+            title_yomigana: self.title_yomigana.clone(),
+            bpm_max: self.bpm_max,
+            bpm_min: self.bpm_min,
+            genre: self.genre,
+            version: self.version,
+            distribution_date: self.distribution_date,
         }
     }
 }
@@ -131,6 +232,27 @@ impl Music {
     pub fn get_inf_ver(&self) -> u8 {
         self.info.inf_ver
     }
+    pub fn get_artist(&self) -> String {
+        self.info.artist_name.clone()
+    }
+    pub fn get_genre(&self) -> u32 {
+        self.info.genre
+    }
+    pub fn get_version(&self) -> u8 {
+        self.info.version
+    }
+    /// get the bpm like `180` or `90-180`, empty if unknown.
+    pub fn get_bpm(&self) -> String {
+        let format_bpm = |bpm: u32| (bpm as f64 / 100.0).to_string();
+        match (self.info.bpm_min, self.info.bpm_max) {
+            (0, 0) => String::new(),
+            (min, max) if min == 0 || min == max => format_bpm(max),
+            (min, max) => format!("{}-{}", format_bpm(min), format_bpm(max)),
+        }
+    }
+    pub fn get_distribution_date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&self.info.distribution_date.to_string(), "%Y%m%d").ok()
+    }
     pub fn get_level(&self, d: impl Into<Difficulty>) -> u8 {
         match d.into() {
             Difficulty::Novice => self.difficulty.novice.level,
@@ -141,17 +263,27 @@ impl Music {
             _ => self.difficulty.infinite.level,
         }
     }
+    /// get all the charts of the music as (difficulty, level),
+    /// the infinite chart is resolved by `inf_ver`.
+    pub fn get_charts(&self) -> Vec<(Difficulty, u8)> {
+        [
+            Difficulty::Novice,
+            Difficulty::Advanced,
+            Difficulty::Exhaust,
+            Difficulty::Infinite,
+            Difficulty::Maximum,
+        ]
+        .into_iter()
+        .map(|d| (d.inf_ver(self.get_inf_ver()), self.get_level(d)))
+        .filter(|&(_, level)| level > 0)
+        .collect()
+    }
     pub fn has_level(&self, level: u8) -> bool {
-        if self.difficulty.novice.level == level
+        self.difficulty.novice.level == level
             || self.difficulty.advanced.level == level
             || self.difficulty.exhaust.level == level
             || self.difficulty.infinite.level == level
             || self.difficulty.maximum.level == level
-        {
-            true
-        } else {
-            false
-        }
     }
 }
 
@@ -164,3 +296,18 @@ impl Clone for Music {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_music_type_round_trip() {
+        for d in Difficulty::ALL {
+            let music_type = d.to_music_type().unwrap();
+            let inf_ver = d.get_inf_ver().unwrap_or_default();
+            assert_eq!(Difficulty::from_chart(music_type, inf_ver), d);
+        }
+        assert_eq!(Difficulty::Unknown.to_music_type(), None);
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// `User` is a player found in the data source.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct User {
+    /// the id of the player, such as the refid of asphyxia.
+    pub refid: String,
+    /// the player name, empty if unknown.
+    pub name: String,
+    /// the number of music records.
+    pub records: usize,
+}
+
+impl User {
+    /// the name if known, else the refid.
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            &self.refid
+        } else {
+            &self.name
+        }
+    }
+}
+
+/// `Profile` is the profile of a player saved by the server.
+///
+/// The fields are named in snake_case, and the camelCase names of asphyxia
+/// and the names of bemaniutils are accepted when deserializing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(alias = "__refid", default)]
+    pub refid: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(alias = "skillLevel", default)]
+    pub skill_level: u32,
+    #[serde(alias = "playCount", alias = "total_plays", default)]
+    pub play_count: u32,
+    #[serde(alias = "packet", default)]
+    pub packets: u32,
+    #[serde(alias = "block", default)]
+    pub blocks: u32,
+}
@@ -1,7 +1,11 @@
 use super::music::{self};
+use crate::Error;
+use chrono::{DateTime, Local};
 use derive_getters::Getters;
-use std::fmt::Display;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt::Display, str::FromStr};
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Grade {
     None,
@@ -35,6 +39,32 @@ impl Display for Grade {
     }
 }
 
+impl Serialize for Grade {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// parse a grade from its display name, ignoring case.
+impl FromStr for Grade {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (0..=10_u8)
+            .map(Grade::from)
+            .find(|g| g.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| Error::OtherError(format!("unknown grade: {}", s)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Grade {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
 // for asyphyxia format
 impl From<u8> for Grade {
     fn from(g: u8) -> Self {
@@ -54,9 +84,9 @@ impl From<u8> for Grade {
     }
 }
 
-impl Into<u8> for Grade {
-    fn into(self) -> u8 {
-        match self {
+impl From<Grade> for u8 {
+    fn from(grade: Grade) -> u8 {
+        match grade {
             Grade::D => 1,
             Grade::C => 2,
             Grade::B => 3,
@@ -92,6 +122,21 @@ impl From<u16> for Grade {
 }
 
 impl Grade {
+    /// the grade of a score.
+    pub fn from_score(score: u32) -> Self {
+        match score {
+            9_900_000.. => Grade::S,
+            9_800_000.. => Grade::AAAPlus,
+            9_700_000.. => Grade::AAA,
+            9_500_000.. => Grade::AAPlus,
+            9_300_000.. => Grade::AA,
+            9_000_000.. => Grade::APlus,
+            8_700_000.. => Grade::A,
+            7_500_000.. => Grade::B,
+            6_500_000.. => Grade::C,
+            _ => Grade::D,
+        }
+    }
     pub fn get_vf_coef(&self) -> u64 {
         match *self {
             Grade::D => 80,
@@ -109,12 +154,15 @@ impl Grade {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// the clear lamps, in the order of the lamp from low to high.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum ClearType {
     None,
     Played,
     Complete,
     HardComplete,
+    /// MAXXIVE COMPLETE, added in EXCEED GEAR.
+    MaxxiveComplete,
     UltimateChain,
     PerfectUltimateChain,
 }
@@ -126,12 +174,39 @@ impl Display for ClearType {
             ClearType::Played => write!(f, "Crash"),
             ClearType::Complete => write!(f, "NC"),
             ClearType::HardComplete => write!(f, "HC"),
+            ClearType::MaxxiveComplete => write!(f, "MXV"),
             ClearType::UltimateChain => write!(f, "UC"),
             ClearType::PerfectUltimateChain => write!(f, "PUC"),
         }
     }
 }
 
+impl Serialize for ClearType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// parse a clear type from its display name, ignoring case.
+impl FromStr for ClearType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (0..=6_u8)
+            .map(ClearType::from)
+            .find(|c| c.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| Error::OtherError(format!("unknown clear type: {}", s)))
+    }
+}
+
+impl<'de> Deserialize<'de> for ClearType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
 // for asyphyxia format
 impl From<u8> for ClearType {
     fn from(t: u8) -> Self {
@@ -141,20 +216,22 @@ impl From<u8> for ClearType {
             3 => ClearType::HardComplete,
             4 => ClearType::UltimateChain,
             5 => ClearType::PerfectUltimateChain,
+            6 => ClearType::MaxxiveComplete,
             _ => ClearType::None,
         }
     }
 }
 
-impl Into<u8> for ClearType {
-    fn into(self) -> u8 {
-        match self {
+impl From<ClearType> for u8 {
+    fn from(clear_type: ClearType) -> u8 {
+        match clear_type {
             ClearType::None => 0,
             ClearType::Played => 1,
             ClearType::Complete => 2,
             ClearType::HardComplete => 3,
             ClearType::UltimateChain => 4,
             ClearType::PerfectUltimateChain => 5,
+            ClearType::MaxxiveComplete => 6,
         }
     }
 }
@@ -173,61 +250,230 @@ impl From<u16> for ClearType {
     }
 }
 
-impl ClearType {
-    pub fn get_vf_coef(&self) -> u64 {
-        match *self {
-            ClearType::Complete => 100,
-            ClearType::HardComplete => 102,
-            ClearType::UltimateChain => 105,
-            ClearType::PerfectUltimateChain => 110,
-            _ => 50,
-        }
-    }
-}
+/// the max score of a chart.
+pub const MAX_SCORE: u32 = 10_000_000;
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Volfoce(u32);
+/// `Volfoce` of a single record or of a player.
+///
+/// The volforce of a single record is on the same scale of the player's,
+/// that is, the player's volforce is the average of the best 50 records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Volfoce {
+    /// volforce * 1000
+    value: u32,
+    /// the number of digits to display after the decimal point.
+    precision: u8,
+}
 
 impl Volfoce {
-    pub fn new(vf: u32) -> Self {
-        Self(vf)
+    pub fn new(vf: u32, precision: u8) -> Self {
+        Self {
+            value: vf,
+            precision,
+        }
     }
     pub fn get_internal(&self) -> u32 {
-        self.0
+        self.value
+    }
+    pub fn get_precision(&self) -> u8 {
+        self.precision
+    }
+}
+
+impl Default for Volfoce {
+    fn default() -> Self {
+        Self::new(0, 3)
     }
 }
 
 impl From<u32> for Volfoce {
     fn from(vf: u32) -> Self {
-        Self(vf)
+        Self::new(vf, 3)
     }
 }
 
 impl Display for Volfoce {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let i = self.0 / 10_u32.pow(3);
-        let d = self.0 % 10_u32.pow(3);
-        if d < 10 {
-            write!(f, "{}.00{}", i, d)
-        } else if d < 100 {
-            write!(f, "{}.0{}", i, d)
-        } else {
-            write!(f, "{}.{}", i, d)
+        let i = self.value / 10_u32.pow(3);
+        if self.precision == 0 {
+            return write!(f, "{}", i);
         }
+        let precision = self.precision.min(3);
+        let d = self.value % 10_u32.pow(3) / 10_u32.pow(3 - precision as u32);
+        write!(f, "{}.{:0width$}", i, d, width = precision as usize)
+    }
+}
+
+/// volforce is serialized as a number such as `17.714`.
+impl Serialize for Volfoce {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.value as f64 / 10_u32.pow(3) as f64)
+    }
+}
+
+/// the precision of the deserialized volforce is 3.
+impl<'de> Deserialize<'de> for Volfoce {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let vf = f64::deserialize(deserializer)?;
+        Ok(Volfoce::from((vf * 1000.0).round() as u32))
     }
 }
 
-/// compute the volforce for a single record
+/// `VolforceRule` is the way to compute volforce, which changes between game versions.
 ///
-/// reference: http://bemaniwiki.com/index.php?SOUND%20VOLTEX%20EXCEED%20GEAR/VOLFORCE
-pub fn compute_volforce(level: u8, score: u32, grade: Grade, clear: ClearType) -> Volfoce {
-    Volfoce(
-        (level as u64 * score as u64 * grade.get_vf_coef() * clear.get_vf_coef() / 10_u64.pow(8))
-            as u32,
-    )
+/// The base of a single record is `level * score / 10,000,000 * grade coef * clear coef`,
+/// and the player's volforce is the sum of the best 50 records after truncation.
+///
+/// reference:
+/// - http://bemaniwiki.com/index.php?SOUND%20VOLTEX%20VIVID%20WAVE/VOLFORCE
+/// - http://bemaniwiki.com/index.php?SOUND%20VOLTEX%20EXCEED%20GEAR/VOLFORCE
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum VolforceRule {
+    /// VIVID WAVE: each record is `base * 2` truncated to integer,
+    /// and the sum is divided by 100 (two decimals).
+    VividWave,
+    /// EXCEED GEAR and later: each record is `base * 20` truncated to integer,
+    /// and the sum is divided by 1000 (three decimals).
+    #[default]
+    ExceedGear,
+}
+
+/// from the game version (5 is VIVID WAVE, 6 is EXCEED GEAR).
+///
+/// The versions before VIVID WAVE have no such volforce rule,
+/// the VIVID WAVE rule is used for them as well.
+impl From<u8> for VolforceRule {
+    fn from(version: u8) -> Self {
+        if version <= 5 {
+            VolforceRule::VividWave
+        } else {
+            VolforceRule::ExceedGear
+        }
+    }
+}
+
+impl VolforceRule {
+    /// the number of digits after the decimal point.
+    pub fn precision(&self) -> u8 {
+        match *self {
+            VolforceRule::VividWave => 2,
+            VolforceRule::ExceedGear => 3,
+        }
+    }
+
+    /// the coefficient of grade (* 100), which is the same in VIVID WAVE and EXCEED GEAR.
+    pub fn grade_coef(&self, grade: Grade) -> u64 {
+        grade.get_vf_coef()
+    }
+
+    /// the coefficient of clear type (* 100), from the VOLFORCE pages referenced above.
+    ///
+    /// EXCEED GEAR raises UC from 1.05 to 1.06 and adds MAXXIVE COMPLETE (1.04),
+    /// which does not exist in VIVID WAVE and is counted as a HC there.
+    pub fn clear_coef(&self, clear: ClearType) -> u64 {
+        match (*self, clear) {
+            (_, ClearType::PerfectUltimateChain) => 110,
+            (VolforceRule::VividWave, ClearType::UltimateChain) => 105,
+            (VolforceRule::ExceedGear, ClearType::UltimateChain) => 106,
+            (VolforceRule::VividWave, ClearType::MaxxiveComplete) => 102,
+            (VolforceRule::ExceedGear, ClearType::MaxxiveComplete) => 104,
+            (_, ClearType::HardComplete) => 102,
+            (_, ClearType::Complete) => 100,
+            _ => 50,
+        }
+    }
+
+    /// the clear types which can be achieved in the version, from low to high.
+    pub fn clear_types(&self) -> Vec<ClearType> {
+        let mut clears = vec![
+            ClearType::Played,
+            ClearType::Complete,
+            ClearType::HardComplete,
+            ClearType::UltimateChain,
+            ClearType::PerfectUltimateChain,
+        ];
+        if *self == VolforceRule::ExceedGear {
+            clears.insert(3, ClearType::MaxxiveComplete);
+        }
+        clears
+    }
+
+    /// compute the volforce for a single record
+    pub fn compute_volforce(
+        &self,
+        level: u8,
+        score: u32,
+        grade: Grade,
+        clear: ClearType,
+    ) -> Volfoce {
+        // base * 10^11
+        let base = level as u64 * score as u64 * self.grade_coef(grade) * self.clear_coef(clear);
+        // the contribution of the record to the player's volforce, in 1/1000
+        let contrib = match *self {
+            VolforceRule::VividWave => base * 2 / 10_u64.pow(11) * 10,
+            VolforceRule::ExceedGear => base * 20 / 10_u64.pow(11),
+        };
+        // scale to the player's volforce
+        Volfoce::new((contrib * 50) as u32, self.precision())
+    }
+
+    /// find the minimum score to get at least `vf` on a chart of `level` with the clear type,
+    /// the grade is decided by the score.
+    /// `None` if it is impossible.
+    pub fn min_score(&self, level: u8, clear: ClearType, vf: Volfoce) -> Option<u32> {
+        let compute = |score: u32| {
+            self.compute_volforce(level, score, Grade::from_score(score), clear)
+                .get_internal()
+        };
+        // only the max score is a PUC, and a max score is always a PUC.
+        let (mut lo, mut hi) = match clear {
+            ClearType::PerfectUltimateChain => (MAX_SCORE, MAX_SCORE),
+            _ => (0, MAX_SCORE - 1),
+        };
+        if compute(hi) < vf.get_internal() {
+            return None;
+        }
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if compute(mid) >= vf.get_internal() {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        Some(lo)
+    }
+
+    /// compute the player's volforce by the best 50 records.
+    pub fn total_volforce(&self, best50: &[FullRecord]) -> Volfoce {
+        let vf_sum: u32 = best50.iter().map(|r| r.get_volforce().get_internal()).sum();
+        Volfoce::new(vf_sum / 50, self.precision())
+    }
+
+    /// the max volforce of a player, with 50 PUCs of level 20 charts.
+    pub fn max_volforce(&self) -> Volfoce {
+        self.compute_volforce(20, MAX_SCORE, Grade::S, ClearType::PerfectUltimateChain)
+    }
+
+    /// the sum of the best 50 chart volforce needed to raise the player's volforce
+    /// from `vf` by `gain`, as the volforce is displayed (truncated to the precision).
+    /// `None` if `gain` is not a positive number, or the volforce would exceed the max.
+    pub fn target_sum(&self, vf: Volfoce, gain: f64) -> Option<u32> {
+        let max = self.max_volforce().get_internal();
+        if !gain.is_finite() || gain <= 0.0 || gain * 1000.0 > max as f64 {
+            return None;
+        }
+        let unit = 10_u32.pow(3 - self.precision() as u32);
+        let vf = vf.get_internal();
+        let target = (vf - vf % unit).checked_add((gain * 1000.0).round() as u32)?;
+        if target > max {
+            return None;
+        }
+        target.checked_mul(50)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct FullRecord {
     pub music_id: u16,
     pub music_name: String,
@@ -236,7 +482,23 @@ pub struct FullRecord {
     pub score: u32,
     pub grade: Grade,
     pub clear_type: ClearType,
+    #[serde(rename = "volforce")]
     pub volfoce: Volfoce,
+    #[serde(default)]
+    pub exscore: u32,
+    /// the rates of button, long and vol (knob) judgments.
+    #[serde(default)]
+    pub button_rate: u16,
+    #[serde(default)]
+    pub long_rate: u16,
+    #[serde(default)]
+    pub vol_rate: u16,
+    /// the time the chart is first played, `None` if unknown.
+    #[serde(default)]
+    pub created_at: Option<DateTime<Local>>,
+    /// the time the record is last updated, `None` if unknown.
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Local>>,
 }
 
 impl Clone for FullRecord {
@@ -250,6 +512,12 @@ impl Clone for FullRecord {
             grade: self.get_grade(),
             clear_type: self.get_clear_type(),
             volfoce: self.get_volforce(),
+            exscore: self.get_exscore(),
+            button_rate: self.get_button_rate(),
+            long_rate: self.get_long_rate(),
+            vol_rate: self.get_vol_rate(),
+            created_at: self.get_created_at(),
+            updated_at: self.get_updated_at(),
         }
     }
 }
@@ -279,51 +547,62 @@ impl FullRecord {
     pub fn get_volforce(&self) -> Volfoce {
         self.volfoce
     }
+    pub fn get_exscore(&self) -> u32 {
+        self.exscore
+    }
+    pub fn get_button_rate(&self) -> u16 {
+        self.button_rate
+    }
+    pub fn get_long_rate(&self) -> u16 {
+        self.long_rate
+    }
+    pub fn get_vol_rate(&self) -> u16 {
+        self.vol_rate
+    }
+    pub fn get_created_at(&self) -> Option<DateTime<Local>> {
+        self.created_at
+    }
+    pub fn get_updated_at(&self) -> Option<DateTime<Local>> {
+        self.updated_at
+    }
 }
 
-#[derive(Clone, Copy, Debug, Getters)]
+#[derive(Clone, Copy, Debug, Default, Getters, Serialize)]
 pub struct LevelStat {
     level: u8,
     /// S
+    #[serde(rename = "s")]
     s_num: u16,
     /// Triple A Plus (AAA+)
+    #[serde(rename = "aaa_plus")]
     tap_num: u16,
     /// Triple A (AAA)
+    #[serde(rename = "aaa")]
     ta_num: u16,
     /// Clear
+    #[serde(rename = "nc")]
     nc_num: u16,
     /// Hard Clear
+    #[serde(rename = "hc")]
     hc_num: u16,
     /// UC
+    #[serde(rename = "uc")]
     uc_num: u16,
     /// PUC
+    #[serde(rename = "puc")]
     puc_num: u16,
     /// played total number
     played: u16,
 }
 
 impl LevelStat {
-    pub fn new(
-        level: u8,
-        s_num: u16,
-        tap_num: u16,
-        ta_num: u16,
-        nc_num: u16,
-        hc_num: u16,
-        uc_num: u16,
-        puc_num: u16,
-        played: u16,
-    ) -> Self {
+    /// an empty stat of `level` with `played` charts, use the `incr_*` methods to count
+    /// the clears and grades.
+    pub fn new(level: u8, played: u16) -> Self {
         Self {
             level,
-            s_num,
-            tap_num,
-            ta_num,
-            nc_num,
-            hc_num,
-            uc_num,
-            puc_num,
             played,
+            ..Default::default()
         }
     }
 
@@ -370,3 +649,219 @@ impl std::ops::Add<LevelStat> for LevelStat {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::music::Difficulty;
+
+    fn record(
+        rule: VolforceRule,
+        level: u8,
+        score: u32,
+        grade: Grade,
+        clear: ClearType,
+    ) -> FullRecord {
+        FullRecord {
+            music_id: 0,
+            music_name: String::new(),
+            difficulty: Difficulty::Maximum,
+            level,
+            score,
+            grade,
+            clear_type: clear,
+            volfoce: rule.compute_volforce(level, score, grade, clear),
+            exscore: 0,
+            button_rate: 0,
+            long_rate: 0,
+            vol_rate: 0,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_rule_from_version() {
+        assert_eq!(VolforceRule::from(1), VolforceRule::VividWave);
+        assert_eq!(VolforceRule::from(5), VolforceRule::VividWave);
+        assert_eq!(VolforceRule::from(6), VolforceRule::ExceedGear);
+    }
+
+    #[test]
+    fn test_single_chart_volforce() {
+        let cases = [
+            // Black night MXM in the README
+            (
+                VolforceRule::ExceedGear,
+                18,
+                9_816_513,
+                Grade::AAAPlus,
+                ClearType::HardComplete,
+                "18.350",
+            ),
+            (
+                VolforceRule::VividWave,
+                18,
+                9_816_513,
+                Grade::AAAPlus,
+                ClearType::HardComplete,
+                "18.00",
+            ),
+            // the max of a level 20 chart: 0.462 in EXCEED GEAR and 0.46 in VIVID WAVE
+            (
+                VolforceRule::ExceedGear,
+                20,
+                MAX_SCORE,
+                Grade::S,
+                ClearType::PerfectUltimateChain,
+                "23.100",
+            ),
+            (
+                VolforceRule::VividWave,
+                20,
+                MAX_SCORE,
+                Grade::S,
+                ClearType::PerfectUltimateChain,
+                "23.00",
+            ),
+            (
+                VolforceRule::ExceedGear,
+                17,
+                9_500_000,
+                Grade::AAPlus,
+                ClearType::Complete,
+                "15.650",
+            ),
+            (
+                VolforceRule::ExceedGear,
+                17,
+                9_000_000,
+                Grade::APlus,
+                ClearType::Played,
+                "6.950",
+            ),
+        ];
+        for (rule, level, score, grade, clear, vf) in cases {
+            assert_eq!(
+                rule.compute_volforce(level, score, grade, clear)
+                    .to_string(),
+                vf
+            );
+        }
+    }
+
+    #[test]
+    fn test_single_chart_truncation() {
+        let rule = VolforceRule::ExceedGear;
+        // 20 * 1.05 * 1.00 * 20 is 420 exactly, and a point less is truncated to 419
+        let vf = rule.compute_volforce(20, MAX_SCORE, Grade::S, ClearType::Complete);
+        assert_eq!(vf.get_internal(), 420 * 50);
+        let vf = rule.compute_volforce(20, MAX_SCORE - 1, Grade::S, ClearType::Complete);
+        assert_eq!(vf.get_internal(), 419 * 50);
+        // 44.1 is truncated to 44 in VIVID WAVE
+        let rule = VolforceRule::VividWave;
+        let vf = rule.compute_volforce(20, MAX_SCORE, Grade::S, ClearType::UltimateChain);
+        assert_eq!(vf.get_internal(), 440 * 50);
+    }
+
+    #[test]
+    fn test_published_volforce() {
+        // the max volforce of a chart of each level, as listed on the VOLFORCE pages
+        for (level, eg, vw) in [
+            (17, "0.392", "0.39"),
+            (18, "0.415", "0.41"),
+            (19, "0.438", "0.43"),
+            (20, "0.462", "0.46"),
+        ] {
+            for (rule, vf) in [
+                (VolforceRule::ExceedGear, eg),
+                (VolforceRule::VividWave, vw),
+            ] {
+                let rec = record(
+                    rule,
+                    level,
+                    MAX_SCORE,
+                    Grade::S,
+                    ClearType::PerfectUltimateChain,
+                );
+                assert_eq!(rule.total_volforce(&[rec]).to_string(), vf);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clear_coef_by_rule() {
+        let eg = VolforceRule::ExceedGear;
+        let vw = VolforceRule::VividWave;
+        assert_eq!(eg.clear_coef(ClearType::UltimateChain), 106);
+        assert_eq!(vw.clear_coef(ClearType::UltimateChain), 105);
+        assert_eq!(eg.clear_coef(ClearType::MaxxiveComplete), 104);
+        assert_eq!(vw.clear_coef(ClearType::MaxxiveComplete), 102);
+        // 18 * 0.99 * 1.05 * 1.06 * 20 is 396.7 in EXCEED GEAR,
+        // 18 * 0.99 * 1.05 * 1.05 * 2 is 39.29 in VIVID WAVE
+        for (rule, vf) in [(eg, "0.396"), (vw, "0.39")] {
+            let rec = record(rule, 18, 9_900_000, Grade::S, ClearType::UltimateChain);
+            assert_eq!(rule.total_volforce(&[rec]).to_string(), vf);
+        }
+        assert_eq!(vw.clear_types().len(), 5);
+        assert!(eg.clear_types().windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_total_volforce() {
+        for (rule, vf, one) in [
+            (VolforceRule::ExceedGear, "18.350", "0.367"),
+            (VolforceRule::VividWave, "18.00", "0.36"),
+        ] {
+            let best50 = (0..50)
+                .map(|_| record(rule, 18, 9_816_513, Grade::AAAPlus, ClearType::HardComplete))
+                .collect::<Vec<FullRecord>>();
+            assert_eq!(rule.total_volforce(&best50).to_string(), vf);
+            assert_eq!(rule.total_volforce(&best50[..1]).to_string(), one);
+            assert_eq!(
+                rule.total_volforce(&[]).to_string(),
+                format!("0.{}", "0".repeat(rule.precision() as usize))
+            );
+        }
+    }
+
+    #[test]
+    fn test_target_sum() {
+        let rule = VolforceRule::ExceedGear;
+        let vf = Volfoce::new(17_005, rule.precision());
+        assert_eq!(rule.target_sum(vf, 0.01), Some(17_015 * 50));
+        // the displayed volforce is truncated to 17.00 in VIVID WAVE
+        let rule = VolforceRule::VividWave;
+        let vf = Volfoce::new(17_005, rule.precision());
+        assert_eq!(rule.target_sum(vf, 0.01), Some(17_010 * 50));
+        // up to the max volforce
+        let rule = VolforceRule::ExceedGear;
+        let vf = Volfoce::new(23_000, rule.precision());
+        assert_eq!(rule.max_volforce().to_string(), "23.100");
+        assert_eq!(rule.target_sum(vf, 0.1), Some(23_100 * 50));
+        assert_eq!(rule.target_sum(vf, 0.101), None);
+        // the gains which would overflow, or are not numbers
+        for gain in [1e7, 1e30, f64::MAX, f64::INFINITY, f64::NAN, 0.0, -1.0] {
+            assert_eq!(rule.target_sum(vf, gain), None, "{}", gain);
+        }
+    }
+
+    #[test]
+    fn test_total_volforce_truncation() {
+        // the sum of 0.313 * 49 and 0.462 is 15.799, which is truncated to 15.79 in display
+        let rule = VolforceRule::ExceedGear;
+        let mut best50 = vec![record(
+            rule,
+            20,
+            MAX_SCORE,
+            Grade::S,
+            ClearType::PerfectUltimateChain,
+        )];
+        best50.extend(
+            (0..49).map(|_| record(rule, 17, 9_500_000, Grade::AAPlus, ClearType::Complete)),
+        );
+        let vf = rule.total_volforce(&best50);
+        assert_eq!(vf.to_string(), "15.799");
+        assert_eq!(Volfoce::new(vf.get_internal(), 2).to_string(), "15.79");
+    }
+}
@@ -0,0 +1,64 @@
+use super::music::Difficulty;
+use super::record::{FullRecord, Volfoce, VolforceRule};
+use crate::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+/// `Snapshot` is all the records of the player at a time,
+/// which is saved to a json file to compare with later.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// the name of the player, empty if unknown.
+    #[serde(default)]
+    pub player: String,
+    pub created_at: DateTime<Local>,
+    pub volforce: Volfoce,
+    /// all the records in volforce order.
+    pub records: Vec<FullRecord>,
+}
+
+impl Snapshot {
+    pub fn new(player: String, records: Vec<FullRecord>, rule: VolforceRule) -> Self {
+        let volforce = rule.total_volforce(&records[..records.len().min(50)]);
+        Snapshot {
+            player,
+            created_at: Local::now(),
+            volforce,
+            records,
+        }
+    }
+
+    /// load a snapshot, the volforce is computed again by `rule`,
+    /// so that it can be compared with the current records.
+    pub fn open(path: impl AsRef<Path>, rule: VolforceRule) -> Result<Self> {
+        let snapshot: Snapshot = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        let mut records = snapshot.records;
+        for r in records.iter_mut() {
+            r.volfoce = rule.compute_volforce(r.level, r.score, r.grade, r.clear_type);
+        }
+        records.sort_by_key(|r| std::cmp::Reverse(r.get_volforce()));
+        Ok(Snapshot {
+            created_at: snapshot.created_at,
+            ..Snapshot::new(snapshot.player, records, rule)
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// find the record of a chart.
+    pub fn get_record(&self, music_id: u16, difficulty: Difficulty) -> Option<&FullRecord> {
+        self.records
+            .iter()
+            .find(|r| r.get_music_id() == music_id && r.get_difficulty() == difficulty)
+    }
+}
@@ -1,6 +1,7 @@
 use std::time::SystemTime;
 
-use crate::model::{music::*, record::*};
+use crate::model::{course::CourseRecord, music::*, record::*};
+use chrono::{DateTime, Local, TimeZone};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -9,6 +10,24 @@ pub struct AsphyxiaRecordDate {
     date: u128,
 }
 
+impl AsphyxiaRecordDate {
+    /// `$$date` is the milliseconds since unix epoch, 0 means unknown.
+    pub fn to_local(&self) -> Option<DateTime<Local>> {
+        if self.date == 0 {
+            return None;
+        }
+        Local.timestamp_millis_opt(self.date as i64).single()
+    }
+}
+
+impl From<Option<DateTime<Local>>> for AsphyxiaRecordDate {
+    fn from(date: Option<DateTime<Local>>) -> Self {
+        AsphyxiaRecordDate {
+            date: date.map_or(0, |d| d.timestamp_millis() as u128),
+        }
+    }
+}
+
 /// One music reocrd is like(but in one line):
 ///
 /// ```json
@@ -35,22 +54,20 @@ pub struct AsphyxiaRecordDate {
 pub struct AsphyxiaRecord {
     #[serde(default)]
     collection: String,
-    #[serde(rename = "mid", default)]
+    // the key fields, a record without them is rejected.
+    #[serde(rename = "mid")]
     music_id: u16,
-    #[serde(rename = "type", default)]
+    #[serde(rename = "type")]
     music_type: u8,
-    #[serde(default)]
     score: u32,
+    #[serde(default)]
+    exscore: u32,
     #[serde(rename = "clear", default)]
     clear_type: u8,
     #[serde(default)]
     grade: u8,
     #[serde(rename = "__refid", default)]
     refid: String,
-
-    // unused for reading data
-    #[serde(rename = "_id", default)]
-    id: String,
     #[serde(rename = "buttonRate", default)]
     button_rate: u8,
     #[serde(rename = "longRate", default)]
@@ -61,6 +78,10 @@ pub struct AsphyxiaRecord {
     create_at: AsphyxiaRecordDate,
     #[serde(rename = "updatedAt", default)]
     update_at: AsphyxiaRecordDate,
+
+    // unused for reading data
+    #[serde(rename = "_id", default)]
+    id: String,
     #[serde(rename = "__a", default)]
     a: String,
     #[serde(rename = "__s", default)]
@@ -86,6 +107,7 @@ impl AsphyxiaRecord {
             music_id,
             music_type,
             score,
+            exscore: 0,
             clear_type,
             grade,
             refid,
@@ -100,6 +122,31 @@ impl AsphyxiaRecord {
         }
     }
 
+    /// convert a record from other data sources to asphyxia format,
+    /// `None` if the difficulty of the record is unknown.
+    pub fn from_full_record(refid: String, rec: &FullRecord, id: String) -> Option<Self> {
+        let mut record = Self::new_sdvx_record(
+            refid,
+            rec.get_music_id(),
+            rec.get_difficulty().to_music_type()?,
+            rec.get_score(),
+            rec.get_clear_type().into(),
+            rec.get_grade().into(),
+            id,
+        );
+        record.exscore = rec.get_exscore();
+        record.button_rate = u8::try_from(rec.get_button_rate()).unwrap_or(u8::MAX);
+        record.long_rate = u8::try_from(rec.get_long_rate()).unwrap_or(u8::MAX);
+        record.vol_rate = u8::try_from(rec.get_vol_rate()).unwrap_or(u8::MAX);
+        if rec.get_created_at().is_some() {
+            record.create_at = rec.get_created_at().into();
+        }
+        if rec.get_updated_at().is_some() {
+            record.update_at = rec.get_updated_at().into();
+        }
+        Some(record)
+    }
+
     pub fn get_collectoin_str(&self) -> &str {
         self.collection.as_str()
     }
@@ -122,7 +169,7 @@ impl AsphyxiaRecord {
         self.clear_type
     }
 
-    pub fn to_full_record(&self, mus: Option<&Music>) -> FullRecord {
+    pub fn to_full_record(&self, mus: Option<&Music>, rule: VolforceRule) -> FullRecord {
         let mut ful_rec = FullRecord {
             music_id: self.get_music_id(),
             music_name: String::from("(NOT FOUND)"),
@@ -132,13 +179,19 @@ impl AsphyxiaRecord {
             grade: Grade::from(self.get_grade()),
             clear_type: ClearType::from(self.get_clear_type()),
             volfoce: Volfoce::default(),
+            exscore: self.exscore,
+            button_rate: self.button_rate as u16,
+            long_rate: self.long_rate as u16,
+            vol_rate: self.vol_rate as u16,
+            created_at: self.create_at.to_local(),
+            updated_at: self.update_at.to_local(),
         };
         if let Some(m) = mus {
             ful_rec.music_name = m.get_name();
-            ful_rec.difficulty = Difficulty::from(self.get_music_type()).inf_ver(m.get_inf_ver());
+            ful_rec.difficulty = Difficulty::from_chart(self.get_music_type(), m.get_inf_ver());
             ful_rec.level = m.get_level(self.get_music_type());
         }
-        ful_rec.volfoce = compute_volforce(
+        ful_rec.volfoce = rule.compute_volforce(
             ful_rec.level,
             ful_rec.score,
             ful_rec.grade,
@@ -147,3 +200,63 @@ impl AsphyxiaRecord {
         ful_rec
     }
 }
+
+/// One Skill Analyzer course reocrd is like(but in one line):
+///
+/// ```json
+/// {
+///     "collection":"course",
+///     "sid":4,
+///     "cid":10,
+///     "score":9512345,
+///     "exscore":0,
+///     "clear":2,
+///     "grade":7,
+///     "rate":8542,
+///     "count":3,
+///     "_id":"0A1SMt6YgLg33p8n",
+///     "createdAt":{"$$date":1633772620910},
+///     "updatedAt":{"$$date":1635585980942},
+///     "__a":"sdvx@asphyxia",
+///     "__s":"plugins_profile",
+///     "__refid":"AB973E24894A6D58"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AsphyxiaCourse {
+    #[serde(rename = "sid", default)]
+    season_id: u16,
+    #[serde(rename = "cid", default)]
+    course_id: u16,
+    #[serde(default)]
+    score: u32,
+    #[serde(default)]
+    exscore: u32,
+    #[serde(rename = "clear", default)]
+    clear_type: u8,
+    #[serde(default)]
+    grade: u8,
+    #[serde(default)]
+    rate: u32,
+    #[serde(default)]
+    count: u32,
+    #[serde(rename = "updatedAt", default)]
+    update_at: AsphyxiaRecordDate,
+}
+
+impl AsphyxiaCourse {
+    pub fn to_course_record(&self) -> CourseRecord {
+        CourseRecord {
+            season_id: self.season_id,
+            course_id: self.course_id,
+            skill_level: CourseRecord::skill_level_of(self.course_id),
+            score: self.score,
+            exscore: self.exscore,
+            clear_type: ClearType::from(self.clear_type),
+            grade: Grade::from(self.grade),
+            rate: self.rate,
+            play_count: self.count,
+            updated_at: self.update_at.to_local(),
+        }
+    }
+}
@@ -1,5 +1,9 @@
 mod asyphyxia;
+mod nedb;
 mod sdvx;
+mod search;
 
-pub use asyphyxia::AsphyxiaRecord;
-pub use sdvx::MusicStore;
+pub use asyphyxia::{AsphyxiaCourse, AsphyxiaRecord};
+pub use nedb::{LoadReport, NeDB};
+pub use sdvx::{normalize_text, MusicStore};
+pub use search::{fold_text, SearchIndex};
@@ -0,0 +1,141 @@
+use crate::{Error, Result};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+};
+
+/// `NeDB` holds the live documents of a NeDB datafile, such as asphyxia's savedata.db.
+///
+/// A NeDB datafile is append-only and every line of it is a JSON document:
+///
+/// - a later line with the same `_id` replaces the earlier document.
+/// - a line like `{"$$deleted":true,"_id":"009SMt6YgLg33p8n"}` removes the document.
+/// - lines like `{"$$indexCreated":{...}}` are index definitions instead of documents.
+pub struct NeDB {
+    /// (line number, document) in the order of their first insertion, `None` if deleted.
+    docs: Vec<Option<(usize, Value)>>,
+    /// lines which are not valid JSON.
+    rejected: Vec<(usize, String)>,
+}
+
+impl NeDB {
+    /// open the datafile and apply all the updates and deletions in it.
+    ///
+    /// Lines which are not valid JSON are rejected,
+    /// and an error is returned on the first of them if `strict` is set.
+    pub fn open(path: impl Into<PathBuf>, strict: bool) -> Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path.into())?), strict)
+    }
+
+    pub fn from_reader(reader: impl BufRead, strict: bool) -> Result<Self> {
+        let mut docs: Vec<Option<(usize, Value)>> = Vec::new();
+        let mut rejected = Vec::new();
+        // `_id` -> index of docs
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_no = i + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let doc: Value = match serde_json::from_str(line.as_str()) {
+                Ok(doc) => doc,
+                Err(e) if strict => return Err(Error::MalformedLineError(line_no, e.to_string())),
+                Err(e) => {
+                    rejected.push((line_no, e.to_string()));
+                    continue;
+                }
+            };
+            if doc.get("$$indexCreated").is_some() || doc.get("$$indexRemoved").is_some() {
+                continue;
+            }
+            let deleted = doc.get("$$deleted").and_then(Value::as_bool) == Some(true);
+            match doc.get("_id").and_then(Value::as_str).map(String::from) {
+                Some(id) => {
+                    let new_doc = if deleted { None } else { Some((line_no, doc)) };
+                    if let Some(&i) = index.get(&id) {
+                        docs[i] = new_doc;
+                    } else if new_doc.is_some() {
+                        index.insert(id, docs.len());
+                        docs.push(new_doc);
+                    }
+                }
+                // documents without `_id` can not be updated or deleted.
+                None if !deleted => docs.push(Some((line_no, doc))),
+                None => {}
+            }
+        }
+        Ok(NeDB { docs, rejected })
+    }
+
+    /// get all the live documents with their line numbers.
+    pub fn documents(&self) -> impl Iterator<Item = (usize, &Value)> {
+        self.docs.iter().flatten().map(|(line, doc)| (*line, doc))
+    }
+
+    /// get the lines which are not valid JSON, as (line number, error).
+    pub fn rejected_lines(&self) -> &[(usize, String)] {
+        &self.rejected
+    }
+}
+
+/// `LoadReport` tells what happened to every line when loading a NeDB datafile.
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    /// (line number, error) of the lines failed to be parsed.
+    rejected: Vec<(usize, String)>,
+    /// collection -> the number of loaded documents.
+    loaded: BTreeMap<String, usize>,
+    /// collection -> the number of skipped documents.
+    skipped: BTreeMap<String, usize>,
+}
+
+impl LoadReport {
+    pub fn new(db: &NeDB) -> Self {
+        LoadReport {
+            rejected: db.rejected_lines().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    pub fn reject(&mut self, line: usize, err: impl Display) {
+        self.rejected.push((line, err.to_string()));
+    }
+    pub fn load(&mut self, collection: &str) {
+        *self.loaded.entry(collection.to_string()).or_insert(0) += 1;
+    }
+    pub fn skip(&mut self, collection: &str) {
+        *self.skipped.entry(collection.to_string()).or_insert(0) += 1;
+    }
+}
+
+impl Display for LoadReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut collections = self
+            .loaded
+            .keys()
+            .chain(self.skipped.keys())
+            .collect::<Vec<_>>();
+        collections.sort();
+        collections.dedup();
+        for c in collections {
+            writeln!(
+                f,
+                "collection {}: {} loaded, {} skipped.",
+                c,
+                self.loaded.get(c).unwrap_or(&0),
+                self.skipped.get(c).unwrap_or(&0)
+            )?;
+        }
+        let mut rejected = self.rejected.iter().collect::<Vec<_>>();
+        rejected.sort();
+        for (line, err) in rejected {
+            writeln!(f, "line {} rejected: {}", line, err)?;
+        }
+        write!(f, "{} line(s) rejected.", self.rejected.len())
+    }
+}
@@ -1,9 +1,81 @@
 use crate::model::music::Music;
 use crate::Result;
+
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_8};
 use quick_xml;
-use rust_fuzzy_search::fuzzy_compare;
 use serde::Deserialize;
-use std::{collections::HashMap, fs::File, io::BufReader, path::PathBuf};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+/// The game replaces the characters which are not in Shift-JIS with some rare kanji,
+/// this table restores them.
+const SUBSTITUTIONS: [(char, char); 26] = [
+    ('驩', 'Ø'),
+    ('齲', '♥'),
+    ('齶', '♡'),
+    ('趁', 'Ǣ'),
+    ('騫', 'á'),
+    ('曦', 'à'),
+    ('驫', 'ā'),
+    ('齷', 'é'),
+    ('骭', 'ü'),
+    ('隍', 'Ü'),
+    ('雋', 'Ǜ'),
+    ('鬻', '♃'),
+    ('鬥', 'Ã'),
+    ('鬆', 'Ý'),
+    ('曩', 'è'),
+    ('罇', 'ê'),
+    ('頽', 'ä'),
+    ('黷', 'ē'),
+    ('瀑', 'À'),
+    ('蹇', '₂'),
+    ('鑷', 'ゔ'),
+    ('彜', 'ū'),
+    ('闃', 'Ā'),
+    ('龕', '€'),
+    ('鑒', '₩'),
+    ('餮', 'Ƶ'),
+];
+
+/// restore the characters substituted by the game.
+pub fn normalize_text(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            SUBSTITUTIONS
+                .iter()
+                .find(|(from, _)| *from == c)
+                .map_or(c, |&(_, to)| to)
+        })
+        .collect()
+}
+
+/// decode the music db, which is usually encoded in Shift-JIS.
+///
+/// The encoding is detected by the xml declaration,
+/// it is Shift-JIS if not declared and the content is not valid UTF-8.
+/// The declaration in the returned xml is replaced with UTF-8.
+fn decode_mdb(bytes: &[u8]) -> String {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(100)]);
+    let declared = head
+        .split_once("?>")
+        .and_then(|(decl, _)| decl.split_once("encoding="))
+        .and_then(|(_, rest)| rest.trim_start().get(1..))
+        .and_then(|rest| rest.split_once(['"', '\'']))
+        .map(|(label, _)| label.to_string());
+    let encoding = match declared
+        .as_ref()
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+    {
+        Some(encoding) => encoding,
+        None if std::str::from_utf8(bytes).is_ok() => UTF_8,
+        None => SHIFT_JIS,
+    };
+    let (xml, _, _) = encoding.decode(bytes);
+    match declared {
+        Some(label) => xml.replacen(&label, "UTF-8", 1),
+        None => xml.into_owned(),
+    }
+}
 
 #[derive(Debug, Deserialize, PartialEq)]
 struct Mdb {
@@ -19,9 +91,11 @@ impl MusicStore {
     fn from_mdb(mdb: Mdb) -> Self {
         let mut music: HashMap<u16, Music> = HashMap::new();
         let mut name_id_map: HashMap<String, u16> = HashMap::new();
-        for m in mdb.music.iter() {
-            music.insert(m.get_id(), m.clone());
+        for mut m in mdb.music.into_iter() {
+            m.info.name = normalize_text(&m.info.name);
+            m.info.artist_name = normalize_text(&m.info.artist_name);
             name_id_map.insert(m.get_name(), m.get_id());
+            music.insert(m.get_id(), m);
         }
         MusicStore { music, name_id_map }
     }
@@ -29,7 +103,8 @@ impl MusicStore {
 
 impl MusicStore {
     pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
-        let mdb: Mdb = quick_xml::de::from_reader(BufReader::new(File::open(path.into())?))?;
+        let xml = decode_mdb(&fs::read(path.into())?);
+        let mdb: Mdb = quick_xml::de::from_str(&xml)?;
         Ok(MusicStore::from_mdb(mdb))
     }
 
@@ -45,19 +120,21 @@ impl MusicStore {
         }
     }
 
-    pub fn get_id_by_name(&self, name: &String, fuzzy: bool) -> Vec<u16> {
-        self.name_id_map
-            .iter()
-            .filter(|(n, _)| {
-                if fuzzy {
-                    name.to_lowercase() == n.to_lowercase()
-                        || fuzzy_compare(&name.to_lowercase(), &n.to_lowercase()) > 0.5
-                } else {
-                    name.as_str() == n.as_str()
-                }
-            })
-            .map(|(_, &id)| id)
-            .collect::<Vec<u16>>()
+    /// get music by ids, in the order of `music_id`.
+    pub fn get_music_by_id(&self, music_id: &[u16]) -> Vec<Music> {
+        let mut music: Vec<Music> = Vec::new();
+        for m in music_id.iter().filter_map(|id| self.get_music_ref(*id)) {
+            if !music.iter().any(|x| x.get_id() == m.get_id()) {
+                music.push(m.clone());
+            }
+        }
+        music
+    }
+
+    pub fn get_all_music(&self) -> Vec<Music> {
+        let mut music = self.music.values().cloned().collect::<Vec<Music>>();
+        music.sort_by_key(|m| m.get_id());
+        music
     }
 
     pub fn get_level_count(&self, level: u8) -> usize {
@@ -67,3 +144,116 @@ impl MusicStore {
             .count()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "ソフラン" in Shift-JIS, the 0x5C in "ソ" is not valid UTF-8.
+    const SOFLAN: [u8; 8] = [0x83, 0x5C, 0x83, 0x74, 0x83, 0x89, 0x83, 0x93];
+    /// "Caf齷" in Shift-JIS, which is "Café" substituted by the game.
+    const CAFE: [u8; 5] = [0x43, 0x61, 0x66, 0xEA, 0x99];
+
+    fn xml(decl: &str, title: &[u8]) -> Vec<u8> {
+        let mut bytes = decl.as_bytes().to_vec();
+        bytes.extend_from_slice(b"<title>");
+        bytes.extend_from_slice(title);
+        bytes.extend_from_slice(b"</title>");
+        bytes
+    }
+
+    #[test]
+    fn test_decode_declared_encoding() {
+        let bytes = xml(r#"<?xml version="1.0" encoding="Shift_JIS"?>"#, &SOFLAN);
+        assert_eq!(
+            decode_mdb(&bytes),
+            r#"<?xml version="1.0" encoding="UTF-8"?><title>ソフラン</title>"#
+        );
+        // single quotes
+        let bytes = xml("<?xml version='1.0' encoding='shift_jis'?>", &SOFLAN);
+        assert_eq!(
+            decode_mdb(&bytes),
+            "<?xml version='1.0' encoding='UTF-8'?><title>ソフラン</title>"
+        );
+    }
+
+    #[test]
+    fn test_decode_undeclared_encoding() {
+        // valid UTF-8 is kept as it is
+        let bytes = xml(r#"<?xml version="1.0"?>"#, "ソフラン".as_bytes());
+        assert_eq!(
+            decode_mdb(&bytes),
+            r#"<?xml version="1.0"?><title>ソフラン</title>"#
+        );
+        // otherwise it is Shift-JIS
+        let bytes = xml(r#"<?xml version="1.0"?>"#, &SOFLAN);
+        assert_eq!(
+            decode_mdb(&bytes),
+            r#"<?xml version="1.0"?><title>ソフラン</title>"#
+        );
+    }
+
+    #[test]
+    fn test_decode_unknown_label() {
+        // an unknown label falls back like an undeclared one, and is rewritten to UTF-8
+        let bytes = xml(r#"<?xml version="1.0" encoding="x-unknown"?>"#, &SOFLAN);
+        assert_eq!(
+            decode_mdb(&bytes),
+            r#"<?xml version="1.0" encoding="UTF-8"?><title>ソフラン</title>"#
+        );
+        let bytes = xml(
+            r#"<?xml version="1.0" encoding="x-unknown"?>"#,
+            "ソフラン".as_bytes(),
+        );
+        assert_eq!(
+            decode_mdb(&bytes),
+            r#"<?xml version="1.0" encoding="UTF-8"?><title>ソフラン</title>"#
+        );
+    }
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("Caf齷"), "Café");
+        assert_eq!(normalize_text("H蹇O"), "H₂O");
+        assert_eq!(normalize_text("齲齶"), "♥♡");
+        assert_eq!(normalize_text("驩 and 鑷"), "Ø and ゔ");
+        // the others are kept
+        assert_eq!(normalize_text("ソフラン"), "ソフラン");
+        assert_eq!(normalize_text(""), "");
+    }
+
+    #[test]
+    fn test_substitutions() {
+        // the substituted kanji are in Shift-JIS, and each of them is restored to one char
+        for (i, &(from, _)) in SUBSTITUTIONS.iter().enumerate() {
+            let (_, _, unmappable) = SHIFT_JIS.encode(&from.to_string());
+            assert!(!unmappable, "{} is not in Shift-JIS", from);
+            assert!(
+                SUBSTITUTIONS[i + 1..]
+                    .iter()
+                    .all(|&(other, _)| other != from),
+                "{} is substituted twice",
+                from
+            );
+        }
+    }
+
+    #[test]
+    fn test_load_shift_jis_mdb() {
+        let mut bytes =
+            br#"<?xml version="1.0" encoding="shift_jis"?><mdb><music id="1"><info><title_name>"#
+                .to_vec();
+        bytes.extend_from_slice(&CAFE);
+        bytes.extend_from_slice(b"</title_name><artist_name>");
+        bytes.extend_from_slice(&SOFLAN);
+        bytes.extend_from_slice(
+            br#"</artist_name><inf_ver>0</inf_ver></info><difficulty><novice><difnum>5</difnum></novice></difficulty></music></mdb>"#,
+        );
+        let mdb: Mdb = quick_xml::de::from_str(&decode_mdb(&bytes)).unwrap();
+        let store = MusicStore::from_mdb(mdb);
+        let music = store.get_music_ref(1).unwrap();
+        assert_eq!(music.get_name(), "Café");
+        assert_eq!(music.get_artist(), "ソフラン");
+        assert_eq!(store.name_id_map.get("Café"), Some(&1));
+    }
+}
@@ -0,0 +1,239 @@
+use crate::config::SearchConfig;
+use crate::Result;
+use rust_fuzzy_search::fuzzy_compare;
+use std::collections::HashMap;
+
+use super::MusicStore;
+
+const HALF_WIDTH_KANA: &str = "｡｢｣､･ｦｧｨｩｪｫｬｭｮｯｰｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝ";
+const FULL_WIDTH_KANA: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+
+/// the min length of a query to match in the middle of a key.
+const MIN_SUBSTRING_LEN: usize = 3;
+
+/// fold the text for searching:
+/// - full-width ascii characters and spaces are turned into half-width ones.
+/// - half-width katakana and hiragana are turned into full-width katakana.
+/// - letters are lowercased.
+pub fn fold_text(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        let c = match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            // hiragana to katakana
+            '\u{3041}'..='\u{3096}' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            // the (han)dakuten of half-width katakana are combined with the previous one
+            'ﾞ' | 'ﾟ' => {
+                let offset = if c == 'ﾞ' { 1 } else { 2 };
+                let voiced = match folded.chars().last() {
+                    Some('ウ') if c == 'ﾞ' => Some('ヴ'),
+                    Some(p) if "ハヒフヘホ".contains(p) => char::from_u32(p as u32 + offset),
+                    Some(p) if c == 'ﾞ' && "カキクケコサシスセソタチツテト".contains(p) => {
+                        char::from_u32(p as u32 + 1)
+                    }
+                    _ => None,
+                };
+                if let Some(v) = voiced {
+                    folded.pop();
+                    folded.push(v);
+                }
+                continue;
+            }
+            _ => HALF_WIDTH_KANA
+                .chars()
+                .position(|k| k == c)
+                .and_then(|i| FULL_WIDTH_KANA.chars().nth(i))
+                .unwrap_or(c),
+        };
+        folded.extend(c.to_lowercase());
+    }
+    folded
+}
+
+/// the similarity of the query and the key, both of them are folded.
+///
+/// 1.0 when they are equal, (0.5, 0.9] when the key contains the query,
+/// and the fuzzy similarity otherwise.
+///
+/// A short query (less than `MIN_SUBSTRING_LEN` characters) only counts as contained
+/// at the start of the key or of a word in it, or it would match almost every key.
+fn similarity(query: &str, key: &str) -> f32 {
+    let contained = if query.chars().count() >= MIN_SUBSTRING_LEN {
+        key.contains(query)
+    } else {
+        key.starts_with(query) || key.split_whitespace().any(|w| w.starts_with(query))
+    };
+    if query == key {
+        1.0
+    } else if contained {
+        0.5 + 0.4 * query.chars().count() as f32 / key.chars().count() as f32
+    } else {
+        fuzzy_compare(query, key)
+    }
+}
+
+/// `SearchIndex` is used to search music by the titles, yomigana, ascii titles
+/// and the aliases from the user's alias file.
+pub struct SearchIndex {
+    /// folded keys and the music ids.
+    keys: Vec<(String, u16)>,
+    /// the max number of results, 0 means no limit.
+    limit: usize,
+}
+
+impl SearchIndex {
+    pub fn new(limit: usize) -> Self {
+        SearchIndex {
+            keys: Vec::new(),
+            limit,
+        }
+    }
+
+    /// build the index of all music in the `music_store`, with the aliases in `conf.alias_path`.
+    pub fn open(music_store: &MusicStore, conf: &SearchConfig) -> Result<Self> {
+        let mut index = SearchIndex::new(conf.limit);
+        for (name, &id) in music_store.name_id_map.iter() {
+            index.add(name, id);
+        }
+        for m in music_store.music.values() {
+            index.add(&m.info.title_yomigana, m.get_id());
+            index.add(&m.info.ascii, m.get_id());
+        }
+        if !conf.alias_path.is_empty() {
+            index.load_aliases(&conf.alias_path)?;
+        }
+        Ok(index)
+    }
+
+    /// add a search key of the music.
+    pub fn add(&mut self, key: &str, music_id: u16) {
+        let key = fold_text(key.trim());
+        if !key.is_empty() {
+            self.keys.push((key, music_id));
+        }
+    }
+
+    /// load the alias file, which is a toml file like:
+    ///
+    /// ```toml
+    /// bof = 229
+    /// "black night" = 1226
+    /// ```
+    pub fn load_aliases(&mut self, path: &str) -> Result<()> {
+        let txt = std::fs::read_to_string(path)?;
+        let aliases: HashMap<String, u16> = toml::from_str(txt.as_str())?;
+        for (alias, music_id) in aliases.iter() {
+            self.add(alias, *music_id);
+        }
+        Ok(())
+    }
+
+    /// search music by `name`, the ids are ranked by similarity.
+    pub fn search(&self, name: &str) -> Vec<u16> {
+        let query = fold_text(name.trim());
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut scores: HashMap<u16, f32> = HashMap::new();
+        for (key, id) in self.keys.iter() {
+            let score = similarity(&query, key);
+            if score > 0.5 {
+                let best = scores.entry(*id).or_insert(score);
+                *best = best.max(score);
+            }
+        }
+        let mut ranked = scores.into_iter().collect::<Vec<(u16, f32)>>();
+        ranked.sort_by(|(id1, s1), (id2, s2)| s2.total_cmp(s1).then(id1.cmp(id2)));
+        if self.limit > 0 {
+            ranked.truncate(self.limit);
+        }
+        ranked.into_iter().map(|(id, _)| id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_width_and_case() {
+        assert_eq!(fold_text("ＢＬＡＣＫ\u{3000}ｎｉｇｈｔ！"), "black night!");
+        assert_eq!(fold_text("Black Night"), "black night");
+    }
+
+    #[test]
+    fn test_fold_kana() {
+        // hiragana and half-width katakana are folded into full-width katakana
+        assert_eq!(fold_text("ぶーす"), "ブース");
+        assert_eq!(fold_text("ｿﾌﾗﾝ｡"), "ソフラン。");
+        // the (han)dakuten are combined with the previous kana
+        assert_eq!(fold_text("ﾌﾞｰｽｵﾌﾞﾌｧｲﾀｰｽﾞ"), "ブースオブファイターズ");
+        assert_eq!(fold_text("ﾊﾟﾋﾟﾌﾟﾍﾟﾎﾟ"), "パピプペポ");
+        assert_eq!(fold_text("ｳﾞｧ"), "ヴァ");
+        assert_eq!(fold_text("ﾃﾞﾄﾞ"), "デド");
+    }
+
+    #[test]
+    fn test_fold_dropped_dakuten() {
+        // the (han)dakuten which can not be combined are dropped silently
+        assert_eq!(fold_text("ｱﾞ"), "ア");
+        assert_eq!(fold_text("ｶﾟ"), "カ");
+        assert_eq!(fold_text("ﾞa"), "a");
+        assert_eq!(fold_text("ﾅﾞﾟ"), "ナ");
+    }
+
+    #[test]
+    fn test_min_substring_len() {
+        // a short query only matches the start of the key or of a word
+        assert!(similarity("bl", "black night") > 0.5);
+        assert!(similarity("ni", "black night") > 0.5);
+        assert!(similarity("ig", "black night") < 0.5);
+        // a long enough query matches anywhere
+        assert!(similarity("igh", "black night") > 0.5);
+        assert_eq!(similarity("black night", "black night"), 1.0);
+    }
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::new(0);
+        // titles, yomigana and ascii titles
+        index.add("Booths of Fighters", 229);
+        index.add("ﾌﾞｰｽｵﾌﾞﾌｧｲﾀｰｽﾞ", 229);
+        index.add("Black night", 1226);
+        index.add("ﾌﾞﾗｯｸﾅｲﾄ", 1226);
+        index.add("Firestorm", 551);
+        index.add("ﾌｧｲｱｽﾄｰﾑ", 551);
+        index
+    }
+
+    #[test]
+    fn test_search_keys() {
+        let index = index();
+        // yomigana in hiragana
+        assert_eq!(index.search("ぶーすおぶふぁいたーず")[0], 229);
+        assert_eq!(index.search("ぶらっく")[0], 1226);
+        // ascii in full-width
+        assert_eq!(index.search("ｆｉｒｅｓｔｏｒｍ")[0], 551);
+        assert!(index.search("").is_empty());
+    }
+
+    #[test]
+    fn test_search_ranking() {
+        let mut index = index();
+        assert_eq!(index.search("fighters"), vec![229]);
+        assert_eq!(index.search("night")[0], 1226);
+        // an alias which equals the query is ranked before the titles containing it
+        let path = std::env::temp_dir().join("ruborute_search_aliases.toml");
+        std::fs::write(&path, "night = 551\nbof = 229\n").unwrap();
+        index.load_aliases(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(index.search("bof")[0], 229);
+        assert_eq!(index.search("night")[..2], [551, 1226]);
+        // the larger part of the key the query covers, the higher it ranks
+        index.add("night of fire", 1);
+        assert_eq!(index.search("fire")[..2], [551, 1]);
+        // the results are limited
+        index.limit = 1;
+        assert_eq!(index.search("night"), vec![551]);
+    }
+}